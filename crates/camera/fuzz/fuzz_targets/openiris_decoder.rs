@@ -0,0 +1,19 @@
+#![no_main]
+
+use camera::OpenIrisDecoder;
+use libfuzzer_sys::fuzz_target;
+
+// feeds arbitrary bytes in chunks, the first byte selects the chunk size
+fuzz_target!(|data: &[u8]| {
+    let Some((&chunk_size, stream)) = data.split_first() else {
+        return;
+    };
+
+    let mut decoder = OpenIrisDecoder::new();
+    for chunk in stream.chunks(chunk_size.max(1) as usize) {
+        decoder.feed(chunk);
+        while let Some(packet) = decoder.next_packet() {
+            assert!(packet.len() <= u16::MAX as usize);
+        }
+    }
+});
@@ -0,0 +1,93 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use camera::OpenIrisDecoder;
+use libfuzzer_sys::fuzz_target;
+
+const ETVR_HEADER_FRAME: &[u8] = &[0xFF, 0xA0, 0xFF, 0xA1];
+// the decoder only buffers a few packets, feeding the whole stream at once
+// would trim intact packets on purpose
+const DEFAULT_CHUNK: usize = ETVR_HEADER_FRAME.len() + 2 + u16::MAX as usize;
+
+#[derive(Debug, Arbitrary)]
+enum Segment {
+    Packet(Vec<u8>),
+    Garbage(Vec<u8>),
+    Truncated { payload: Vec<u8>, cut: usize },
+    BogusLength { payload: Vec<u8>, len: u16 },
+}
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    segments: Vec<Segment>,
+    chunks: Vec<u8>,
+}
+
+// real jpeg data never contains the header marker, without 0xA1 bytes
+// neither payloads nor garbage can form one by accident
+fn sanitize(data: &[u8]) -> Vec<u8> {
+    data.iter()
+        .map(|&byte| if byte == 0xA1 { 0xA2 } else { byte })
+        .collect()
+}
+
+fn packet(payload: &[u8]) -> Vec<u8> {
+    let len = (payload.len() as u16).to_le_bytes();
+    [ETVR_HEADER_FRAME, &len, payload].concat()
+}
+
+// builds a stream from valid and broken segments and checks that every
+// intact packet is decoded in order
+fuzz_target!(|input: Input| {
+    let mut stream = Vec::new();
+    let mut intact = Vec::new();
+    for segment in &input.segments {
+        match segment {
+            Segment::Packet(payload) => {
+                let mut payload = sanitize(payload);
+                payload.truncate(u16::MAX as usize);
+                stream.extend(packet(&payload));
+                intact.push(payload);
+            }
+            Segment::Garbage(garbage) => stream.extend(sanitize(garbage)),
+            Segment::Truncated { payload, cut } => {
+                let mut payload = sanitize(payload);
+                payload.truncate(u16::MAX as usize);
+                let data = packet(&payload);
+                stream.extend(&data[..cut % data.len()]);
+            }
+            Segment::BogusLength { payload, len } => {
+                stream.extend(ETVR_HEADER_FRAME);
+                stream.extend(len.to_le_bytes());
+                stream.extend(sanitize(payload));
+            }
+        }
+    }
+    // the device keeps streaming, held back payloads are released by this
+    stream.extend(packet(&[0xFF, 0xD9]));
+
+    let mut decoder = OpenIrisDecoder::new();
+    let mut packets = Vec::new();
+    let mut rest = stream.as_slice();
+    let mut chunks = input
+        .chunks
+        .iter()
+        .map(|&size| size.max(1) as usize)
+        .cycle();
+    while !rest.is_empty() {
+        let size = chunks.next().unwrap_or(DEFAULT_CHUNK).min(rest.len());
+        let (chunk, tail) = rest.split_at(size);
+        decoder.feed(chunk);
+        packets.extend(std::iter::from_fn(|| decoder.next_packet()));
+        rest = tail;
+    }
+
+    let mut packets = packets.iter();
+    for payload in &intact {
+        assert!(
+            packets.any(|packet| packet == payload),
+            "lost intact packet of {} bytes",
+            payload.len()
+        );
+    }
+});
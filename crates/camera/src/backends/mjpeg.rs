@@ -0,0 +1,736 @@
+// Pure Rust client for MJPEG streams served as `multipart/x-mixed-replace`,
+// e.g. by the wireless OpenIris firmware on port 81.
+// Both the chunked transfer encoding and the multipart body are decoded
+// incrementally, a read timeout therefore never loses already received bytes.
+
+use std::{
+    io::{self, Read, Write},
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use log::{debug, info, trace, warn};
+
+use crate::{CameraHandler, CameraSource, CameraState, Frame};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+// upper bound for the response head and a single part, protects against
+// servers that never send a boundary
+const MAX_HEAD_SIZE: usize = 16 * 1024;
+const MAX_PART_SIZE: usize = 4 * 1024 * 1024;
+const READ_BUF_SIZE: usize = 16 * 1024;
+
+#[derive(Debug)]
+struct Connection {
+    stream: TcpStream,
+    chunked: Option<ChunkedDecoder>,
+    parts: MultipartDecoder,
+}
+
+#[derive(Debug)]
+pub struct MjpegHttpCamera {
+    connection: Option<Connection>,
+    read_timeout: Duration,
+}
+
+impl MjpegHttpCamera {
+    /// Overrides the time a single read may block before reporting
+    /// [`CameraState::Timeout`]
+    pub fn with_read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = read_timeout;
+        self
+    }
+}
+
+impl CameraHandler for MjpegHttpCamera {
+    fn init() -> Self
+    where
+        Self: Sized,
+    {
+        Self {
+            connection: None,
+            read_timeout: READ_TIMEOUT,
+        }
+    }
+
+    fn get_frame(&mut self) -> Result<Frame, CameraState> {
+        match self.connection.as_mut() {
+            Some(connection) => connection.next_frame(),
+            None => Err(CameraState::Disconnected),
+        }
+    }
+
+    fn connect(&mut self, source: String) -> Result<(), CameraState> {
+        if self.connection.is_some() {
+            self.disconnect();
+        }
+
+        info!("connecting to {source}");
+        let mut connection = Connection::open(&source, self.read_timeout)?;
+
+        // make sure the server actually streams jpeg frames
+        let frame = connection.next_frame()?;
+        debug!(
+            "connected to {source}, first frame {}x{}",
+            frame.width, frame.height
+        );
+        self.connection = Some(connection);
+
+        Ok(())
+    }
+
+    fn disconnect(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            let _ = connection.stream.shutdown(std::net::Shutdown::Both);
+        }
+    }
+}
+
+impl Connection {
+    fn open(url: &str, read_timeout: Duration) -> Result<Connection, CameraState> {
+        let source = CameraSource::parse(url)?;
+        if source.scheme() != "http" {
+            warn!("unsupported scheme for mjpeg stream: {url}");
+            return Err(CameraState::InvalidSource);
+        }
+
+        let host = source.host();
+        let addresses = socket_address(host)
+            .to_socket_addrs()
+            .map_err(|e| CameraState::Error(format!("Failed to resolve {host}: {e}")))?;
+        let mut stream = connect(addresses)?;
+        stream
+            .set_read_timeout(Some(read_timeout))
+            .map_err(io_error_state)?;
+
+        let path = request_target(url, host);
+        let request = format!(
+            "GET {path} HTTP/1.1\r\nHost: {host}\r\nAccept: multipart/x-mixed-replace, image/jpeg\r\nConnection: close\r\n\r\n"
+        );
+        stream
+            .write_all(request.as_bytes())
+            .map_err(io_error_state)?;
+
+        let (head, body) = read_head(&mut stream)?;
+        let response = Response::parse(&head)?;
+        if response.status != 200 {
+            return Err(CameraState::Error(format!(
+                "Unexpected http status {} from {url}",
+                response.status
+            )));
+        }
+
+        let content_type = response.header("content-type").unwrap_or_default();
+        if !content_type
+            .to_ascii_lowercase()
+            .starts_with("multipart/x-mixed-replace")
+        {
+            warn!("unexpected content type for mjpeg stream: {content_type}");
+            return Err(CameraState::InvalidSource);
+        }
+
+        let chunked = response
+            .header("transfer-encoding")
+            .is_some_and(|encoding| encoding.eq_ignore_ascii_case("chunked"));
+        let mut connection = Connection {
+            stream,
+            chunked: chunked.then(ChunkedDecoder::default),
+            parts: MultipartDecoder::new(boundary(content_type)),
+        };
+        connection.feed(&body)?;
+
+        Ok(connection)
+    }
+
+    fn feed(&mut self, data: &[u8]) -> Result<(), CameraState> {
+        match self.chunked.as_mut() {
+            Some(chunked) => {
+                let data = chunked.feed(data)?;
+                self.parts.feed(&data);
+            }
+            None => self.parts.feed(data),
+        }
+
+        Ok(())
+    }
+
+    fn next_frame(&mut self) -> Result<Frame, CameraState> {
+        let mut buf = [0u8; READ_BUF_SIZE];
+
+        loop {
+            if let Some(part) = self.parts.next_part() {
+                return Ok(Frame::jpeg(part));
+            }
+
+            let read = self.stream.read(&mut buf).map_err(io_error_state)?;
+            if read == 0 {
+                debug!("mjpeg stream closed by server");
+                return Err(CameraState::Disconnected);
+            }
+
+            trace!("read {read} bytes from mjpeg stream");
+            self.feed(&buf[..read])?;
+        }
+    }
+}
+
+/// Reads until the end of the response head
+/// - Returns the head and any body bytes received along with it
+fn read_head(stream: &mut TcpStream) -> Result<(String, Vec<u8>), CameraState> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 1024];
+
+    loop {
+        if let Some(end) = find(&data, b"\r\n\r\n") {
+            let body = data.split_off(end + 4);
+            return Ok((String::from_utf8_lossy(&data).into_owned(), body));
+        }
+        if data.len() > MAX_HEAD_SIZE {
+            return Err(CameraState::Error("http response head too large".into()));
+        }
+
+        let read = stream.read(&mut buf).map_err(io_error_state)?;
+        if read == 0 {
+            return Err(CameraState::Disconnected);
+        }
+        data.extend_from_slice(&buf[..read]);
+    }
+}
+
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+}
+
+impl Response {
+    fn parse(head: &str) -> Result<Response, CameraState> {
+        let mut lines = head.lines();
+        let status = lines
+            .next()
+            .and_then(|line| {
+                let mut parts = line.split_whitespace();
+                parts
+                    .next()
+                    .filter(|version| version.starts_with("HTTP/"))?;
+                parts.next()?.parse().ok()
+            })
+            .ok_or_else(|| CameraState::Error("malformed http status line".into()))?;
+
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_ascii_lowercase(), value.trim().to_string()))
+            .collect();
+
+        Ok(Response { status, headers })
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Extracts the boundary parameter of a multipart content type
+/// - Quotes and a leading `--`, which some servers include, are removed
+fn boundary(content_type: &str) -> Option<String> {
+    content_type
+        .split(';')
+        .filter_map(|param| param.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| value.trim().trim_matches('"'))
+        .map(|value| value.strip_prefix("--").unwrap_or(value).to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Decodes a `Transfer-Encoding: chunked` body incrementally
+#[derive(Debug, Default)]
+struct ChunkedDecoder {
+    buf: Vec<u8>,
+    // bytes left in the current chunk, `None` while waiting for a size line
+    remaining: Option<usize>,
+}
+
+impl ChunkedDecoder {
+    fn feed(&mut self, data: &[u8]) -> Result<Vec<u8>, CameraState> {
+        self.buf.extend_from_slice(data);
+        let mut output = Vec::with_capacity(self.buf.len());
+        let mut pos = 0;
+
+        loop {
+            match self.remaining {
+                Some(0) => {
+                    // every chunk is terminated by a line break
+                    let Some(end) = find(&self.buf[pos..], b"\n") else {
+                        break;
+                    };
+                    pos += end + 1;
+                    self.remaining = None;
+                }
+                Some(remaining) => {
+                    let take = remaining.min(self.buf.len() - pos);
+                    if take == 0 {
+                        break;
+                    }
+                    output.extend_from_slice(&self.buf[pos..pos + take]);
+                    pos += take;
+                    self.remaining = Some(remaining - take);
+                }
+                None => {
+                    let Some(end) = find(&self.buf[pos..], b"\n") else {
+                        if self.buf.len() - pos > MAX_HEAD_SIZE {
+                            return Err(CameraState::Error("malformed chunk size".into()));
+                        }
+                        break;
+                    };
+                    let line = String::from_utf8_lossy(&self.buf[pos..pos + end]);
+                    // chunk extensions are separated by a semicolon
+                    let size = line.split(';').next().unwrap_or_default().trim();
+                    let size = usize::from_str_radix(size, 16).map_err(|_| {
+                        CameraState::Error(format!("malformed chunk size: {size:?}"))
+                    })?;
+                    pos += end + 1;
+
+                    if size == 0 {
+                        // the server closes the connection right after
+                        debug!("received last chunk");
+                        pos = self.buf.len();
+                        break;
+                    }
+                    self.remaining = Some(size);
+                }
+            }
+        }
+
+        self.buf.drain(..pos);
+        Ok(output)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PartState {
+    // waiting for the next boundary delimiter
+    Boundary,
+    Headers { content_length: Option<usize> },
+    Body { content_length: Option<usize> },
+}
+
+/// Splits a `multipart/x-mixed-replace` body into its parts incrementally
+#[derive(Debug)]
+struct MultipartDecoder {
+    // `--` followed by the boundary, learned from the stream if not announced
+    delimiter: Option<Vec<u8>>,
+    buf: Vec<u8>,
+    state: PartState,
+}
+
+impl MultipartDecoder {
+    fn new(boundary: Option<String>) -> Self {
+        Self {
+            delimiter: boundary.map(|boundary| format!("--{boundary}").into_bytes()),
+            buf: Vec::new(),
+            state: PartState::Boundary,
+        }
+    }
+
+    fn feed(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+    }
+
+    /// Returns the body of the next complete part
+    fn next_part(&mut self) -> Option<Vec<u8>> {
+        loop {
+            match self.state {
+                PartState::Boundary => self.find_boundary()?,
+                PartState::Headers { content_length } => self.read_header(content_length)?,
+                PartState::Body { content_length } => {
+                    if let Some(part) = self.read_body(content_length)? {
+                        return Some(part);
+                    }
+                }
+            }
+        }
+    }
+
+    fn find_boundary(&mut self) -> Option<()> {
+        let delimiter = match &self.delimiter {
+            Some(delimiter) => delimiter.clone(),
+            None => self.learn_delimiter()?,
+        };
+
+        let Some(start) = find(&self.buf, &delimiter) else {
+            // keep enough bytes to match a delimiter split across reads
+            let keep = delimiter.len().min(self.buf.len());
+            self.buf.drain(..self.buf.len() - keep);
+            return None;
+        };
+
+        // the rest of the delimiter line, possibly transport padding
+        let line_end = find(&self.buf[start..], b"\n")? + start;
+        self.buf.drain(..=line_end);
+        self.state = PartState::Headers {
+            content_length: None,
+        };
+
+        Some(())
+    }
+
+    /// Adopts the first line starting with `--` as delimiter
+    fn learn_delimiter(&mut self) -> Option<Vec<u8>> {
+        let mut pos = 0;
+        while let Some(end) = find(&self.buf[pos..], b"\n") {
+            let line = trim_line(&self.buf[pos..pos + end]);
+            if line.len() > 2 && line.starts_with(b"--") {
+                let delimiter = line.to_vec();
+                debug!(
+                    "using boundary {} found in stream",
+                    String::from_utf8_lossy(&delimiter)
+                );
+                self.delimiter = Some(delimiter.clone());
+                return Some(delimiter);
+            }
+            pos += end + 1;
+        }
+
+        self.buf.drain(..pos);
+        None
+    }
+
+    fn read_header(&mut self, content_length: Option<usize>) -> Option<()> {
+        let end = find(&self.buf, b"\n")?;
+        let line = String::from_utf8_lossy(trim_line(&self.buf[..end])).into_owned();
+        self.buf.drain(..=end);
+
+        self.state = match line.split_once(':') {
+            // an empty line terminates the headers
+            None if line.is_empty() => PartState::Body { content_length },
+            Some((name, value)) if name.trim().eq_ignore_ascii_case("content-length") => {
+                PartState::Headers {
+                    content_length: value.trim().parse().ok(),
+                }
+            }
+            _ => PartState::Headers { content_length },
+        };
+
+        Some(())
+    }
+
+    fn read_body(&mut self, content_length: Option<usize>) -> Option<Option<Vec<u8>>> {
+        match content_length {
+            Some(len) if len > MAX_PART_SIZE => {
+                warn!("discarding oversized part of {len} bytes");
+                self.state = PartState::Boundary;
+                Some(None)
+            }
+            Some(len) => {
+                if self.buf.len() < len {
+                    return None;
+                }
+                let part = self.buf.drain(..len).collect();
+                self.state = PartState::Boundary;
+                Some(Some(part))
+            }
+            None => {
+                let delimiter = self.delimiter.as_ref()?;
+                let Some(end) = find(&self.buf, delimiter) else {
+                    if self.buf.len() > MAX_PART_SIZE {
+                        warn!("discarding part without boundary");
+                        self.buf.clear();
+                        self.state = PartState::Boundary;
+                    }
+                    return None;
+                };
+
+                // the line break before the delimiter belongs to the boundary
+                let mut part: Vec<u8> = self.buf.drain(..end).collect();
+                if part.ends_with(b"\r\n") {
+                    part.truncate(part.len() - 2);
+                } else if part.ends_with(b"\n") {
+                    part.truncate(part.len() - 1);
+                }
+                self.state = PartState::Boundary;
+                Some(Some(part))
+            }
+        }
+    }
+}
+
+fn trim_line(line: &[u8]) -> &[u8] {
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Appends the default port unless the host already names one
+/// - IPv6 addresses are bracketed, e.g. `[fe80::1]:81`
+fn socket_address(host: &str) -> String {
+    if let Some(address) = host.strip_prefix('[') {
+        match address.split_once(']') {
+            Some((_, "")) => format!("{host}:80"),
+            _ => host.to_string(),
+        }
+    } else if host.matches(':').count() > 1 {
+        // a bare IPv6 address, every colon belongs to the address
+        format!("[{host}]:80")
+    } else if host.contains(':') {
+        host.to_string()
+    } else {
+        format!("{host}:80")
+    }
+}
+
+/// Tries every resolved address in turn, e.g. both the IPv6 and the IPv4
+/// address of a `.local` name
+fn connect(addresses: impl Iterator<Item = SocketAddr>) -> Result<TcpStream, CameraState> {
+    let mut last_error = CameraState::InvalidSource;
+    for address in addresses {
+        match TcpStream::connect_timeout(&address, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => {
+                debug!("failed to connect to {address}: {e}");
+                last_error = io_error_state(e);
+            }
+        }
+    }
+
+    Err(last_error)
+}
+
+/// Returns the path and query of the url, forwarded verbatim to the server
+fn request_target(url: &str, host: &str) -> String {
+    let target = url
+        .split_once("://")
+        .map(|(_, rest)| &rest[host.len()..])
+        .and_then(|target| target.split('#').next())
+        .unwrap_or_default();
+
+    if target.starts_with('/') {
+        target.to_string()
+    } else {
+        // either empty or only a query
+        format!("/{target}")
+    }
+}
+
+/// Maps socket errors to the matching camera state
+fn io_error_state(e: io::Error) -> CameraState {
+    match e.kind() {
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => CameraState::Timeout,
+        io::ErrorKind::ConnectionRefused
+        | io::ErrorKind::ConnectionReset
+        | io::ErrorKind::ConnectionAborted
+        | io::ErrorKind::BrokenPipe
+        | io::ErrorKind::NotConnected
+        | io::ErrorKind::UnexpectedEof => CameraState::Disconnected,
+        _ => CameraState::Error(format!("http stream error: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::BufRead, net::TcpListener, thread::JoinHandle};
+
+    use super::*;
+
+    const JPEG_A: &[u8] = &[0xFF, 0xD8, 0x01, 0x02, 0xFF, 0xD9];
+    const JPEG_B: &[u8] = &[0xFF, 0xD8, 0x03, 0x0D, 0x0A, 0x04, 0xFF, 0xD9];
+
+    /// Serves a single connection with the given response head and body
+    /// chunks, returning the request line it received
+    fn serve(
+        head: &'static str,
+        chunks: Vec<Vec<u8>>,
+        linger: Duration,
+    ) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/stream?eye=left", listener.local_addr().unwrap());
+
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = io::BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            let mut stream = stream;
+            stream.write_all(head.as_bytes()).unwrap();
+            for chunk in chunks {
+                stream.write_all(&chunk).unwrap();
+                stream.flush().unwrap();
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            std::thread::sleep(linger);
+
+            request_line
+        });
+
+        (url, server)
+    }
+
+    fn part(boundary: &str, jpeg: &[u8], content_length: bool) -> Vec<u8> {
+        let mut part = format!("--{boundary}\r\nContent-Type: image/jpeg\r\n").into_bytes();
+        if content_length {
+            part.extend(format!("Content-Length: {}\r\n", jpeg.len()).bytes());
+        }
+        part.extend(b"\r\n");
+        part.extend(jpeg);
+        part.extend(b"\r\n");
+        part
+    }
+
+    fn chunk(data: &[u8]) -> Vec<u8> {
+        let mut chunk = format!("{:x};ext=1\r\n", data.len()).into_bytes();
+        chunk.extend(data);
+        chunk.extend(b"\r\n");
+        chunk
+    }
+
+    #[test]
+    fn test_stream_with_content_length() {
+        let body = [
+            part("frame", JPEG_A, true),
+            part("frame", JPEG_B, true),
+            part("frame", JPEG_A, true),
+        ]
+        .concat();
+        // deliver the body in awkward pieces to exercise the incremental parser
+        let chunks = body.chunks(7).map(<[u8]>::to_vec).collect();
+        let (url, server) = serve(
+            "HTTP/1.1 200 OK\r\nContent-Type: multipart/x-mixed-replace; boundary=frame\r\n\r\n",
+            chunks,
+            Duration::ZERO,
+        );
+
+        let mut camera = MjpegHttpCamera::init();
+        camera.connect(url).expect("stub stream should connect");
+
+        // the first frame is consumed while connecting
+        assert_eq!(camera.get_frame().unwrap().data, JPEG_B);
+        assert_eq!(camera.get_frame().unwrap().data, JPEG_A);
+        assert_eq!(camera.get_frame().err(), Some(CameraState::Disconnected));
+
+        let request_line = server.join().unwrap();
+        assert_eq!(request_line.trim(), "GET /stream?eye=left HTTP/1.1");
+    }
+
+    #[test]
+    fn test_socket_address() {
+        assert_eq!(
+            socket_address("openiristracker.local"),
+            "openiristracker.local:80"
+        );
+        assert_eq!(socket_address("192.168.0.12:81"), "192.168.0.12:81");
+        assert_eq!(socket_address("[fe80::1]"), "[fe80::1]:80");
+        assert_eq!(socket_address("[fe80::1]:81"), "[fe80::1]:81");
+        assert_eq!(socket_address("fe80::1"), "[fe80::1]:80");
+
+        let resolved: Vec<_> = socket_address("[::1]").to_socket_addrs().unwrap().collect();
+        assert_eq!(resolved, vec!["[::1]:80".parse().unwrap()]);
+    }
+
+    #[test]
+    fn test_request_target() {
+        let target = |url: &str| request_target(url, CameraSource::parse(url).unwrap().host());
+        assert_eq!(target("http://tracker:81"), "/");
+        assert_eq!(target("http://tracker:81/"), "/");
+        assert_eq!(target("http://tracker:81?eye=left"), "/?eye=left");
+        assert_eq!(
+            target("http://tracker:81/stream?eye=left#top"),
+            "/stream?eye=left"
+        );
+        assert_eq!(target("http://[::1]:81/stream"), "/stream");
+    }
+
+    #[test]
+    fn test_connect_tries_all_addresses() {
+        let refused = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap()
+        };
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let listening = listener.local_addr().unwrap();
+
+        let stream =
+            connect([refused, listening].into_iter()).expect("second address should connect");
+        assert_eq!(stream.peer_addr().unwrap(), listening);
+        assert_eq!(
+            connect([refused].into_iter()).err(),
+            Some(CameraState::Disconnected)
+        );
+        assert_eq!(
+            connect(std::iter::empty()).err(),
+            Some(CameraState::InvalidSource)
+        );
+    }
+
+    #[test]
+    fn test_chunked_stream_without_content_length() {
+        // boundary announced with a leading `--` and quoted, parts without
+        // content length are split at the next delimiter
+        let body = [
+            b"preamble\r\n".to_vec(),
+            part("--boundarydonotcross", JPEG_A, false),
+            part("--boundarydonotcross", JPEG_B, false),
+            b"--boundarydonotcross--\r\n".to_vec(),
+        ]
+        .concat();
+        let chunks = body.chunks(11).map(chunk).collect();
+        let (url, server) = serve(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nContent-Type: multipart/x-mixed-replace;boundary=\"--boundarydonotcross\"\r\n\r\n",
+            chunks,
+            Duration::ZERO,
+        );
+
+        let mut camera = MjpegHttpCamera::init();
+        camera.connect(url).expect("stub stream should connect");
+        assert_eq!(camera.get_frame().unwrap().data, JPEG_B);
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_read_timeout() {
+        let (url, server) = serve(
+            "HTTP/1.1 200 OK\r\nContent-Type: multipart/x-mixed-replace\r\n\r\n",
+            vec![
+                part("learned", JPEG_A, true),
+                b"--learned\r\nContent-Le".to_vec(),
+            ],
+            Duration::from_millis(300),
+        );
+
+        let mut camera = MjpegHttpCamera::init().with_read_timeout(Duration::from_millis(50));
+        camera.connect(url).expect("stub stream should connect");
+        assert_eq!(camera.get_frame().err(), Some(CameraState::Timeout));
+
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_rejects_non_multipart_response() {
+        let (url, server) = serve(
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n",
+            vec![],
+            Duration::ZERO,
+        );
+        assert!(MjpegHttpCamera::init().connect(url).is_err());
+        server.join().unwrap();
+
+        let (url, server) = serve(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\r\n",
+            vec![],
+            Duration::ZERO,
+        );
+        assert_eq!(
+            MjpegHttpCamera::init().connect(url).err(),
+            Some(CameraState::InvalidSource)
+        );
+        server.join().unwrap();
+    }
+}
@@ -1,11 +1,26 @@
+mod mjpeg;
 mod noop;
 mod opencv;
 mod openiris;
+mod replay;
+mod synthetic;
 
+pub use mjpeg::MjpegHttpCamera;
 pub use noop::NoOpCamera;
 pub use opencv::OpenCVCamera;
-pub use openiris::OpenIrisCamera;
+pub use openiris::{
+    BaudRate, DeviceEvent, DeviceInfo, EspFlasher, FirmwarePolicy, FirmwareVersion, FlashError,
+    FlashProgress, OpenIrisCamera, OpenIrisCommand, OpenIrisCommander, OpenIrisDecoder,
+    OpenIrisDevice, OpenIrisDiscovery, OpenIrisMessage, PortOpener, ProvisioningError, ResetMode,
+    SerialConfig, SerialWatcher, StreamingMode, UsbBridge, WifiProvisioning, WifiStatus,
+};
+pub use replay::{ReplayCamera, ReplayControl, ReplayMode};
+pub use synthetic::{EyeState, GazeScript, GroundTruth, SyntheticEye, SyntheticEyeCamera};
 
+/// The original backends, only used by the deprecated [`crate::Camera::new`]
+/// - Backends are registered in [`crate::BackendRegistry`] instead, which
+///   also covers backends outside of this crate
+#[deprecated(note = "use `Camera::open` or `Camera::open_with` with a `BackendRegistry`")]
 pub enum CameraHandlers {
     NoOp,
     OpenCV,
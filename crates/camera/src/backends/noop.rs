@@ -14,8 +14,8 @@ impl CameraHandler for NoOpCamera {
         Self {}
     }
 
-    fn get_frame(&mut self) -> Result<Vec<u8>, CameraState> {
-        Ok(vec![])
+    fn get_frame(&mut self) -> Result<Frame, CameraState> {
+        Frame::packed(vec![], 0, 0, PixelFormat::Gray8)
     }
 
     fn connect(&mut self, _source: String) -> Result<(), CameraState> {
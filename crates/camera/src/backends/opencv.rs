@@ -5,6 +5,7 @@
 // for a massvice refactor to nuke OpenCV
 
 use opencv::{
+    core::{CV_8UC1, CV_8UC3},
     prelude::*,
     videoio::{CAP_FFMPEG, VideoCapture},
 };
@@ -23,11 +24,21 @@ enum InternalState {
 #[derive(Debug)]
 pub struct OpenCVCamera {
     state: InternalState,
+    api_preference: i32,
 }
 
 unsafe impl Send for OpenCVCamera {}
 unsafe impl Sync for OpenCVCamera {}
 
+impl OpenCVCamera {
+    /// Selects the OpenCV capture API used to open sources, `CAP_FFMPEG` by
+    /// default
+    pub fn with_api_preference(mut self, api_preference: i32) -> Self {
+        self.api_preference = api_preference;
+        self
+    }
+}
+
 impl CameraHandler for OpenCVCamera {
     fn init() -> Self
     where
@@ -35,10 +46,11 @@ impl CameraHandler for OpenCVCamera {
     {
         Self {
             state: InternalState::Waiting,
+            api_preference: CAP_FFMPEG,
         }
     }
 
-    fn get_frame(&mut self) -> Result<Vec<u8>, CameraState> {
+    fn get_frame(&mut self) -> Result<Frame, CameraState> {
         match self.state {
             InternalState::Connected {
                 ref mut capture, ..
@@ -48,7 +60,7 @@ impl CameraHandler for OpenCVCamera {
                 match capture.read(&mut mat) {
                     Ok(true) => {
                         if mat.size().unwrap().width > 0 && mat.size().unwrap().height > 0 {
-                            return Ok(mat.data_bytes().unwrap().to_vec());
+                            return frame_from_mat(&mat);
                         }
 
                         Err(CameraState::ReadFailed)
@@ -70,16 +82,20 @@ impl CameraHandler for OpenCVCamera {
                 log::trace!("Skipping connection, already connected to {source}");
                 Ok(())
             }
-            InternalState::Connected { .. } => {
-                // todo connect to other source here?
-                Ok(())
+            InternalState::Connected {
+                source: ref current_source,
+                ..
+            } => {
+                log::debug!("Switching source from {current_source} to {source}");
+                self.disconnect();
+                self.connect(source)
             }
             InternalState::Waiting => {
                 // TODO: Ping the host to see if it is alive
 
                 let mut capture = VideoCapture::default().unwrap();
 
-                if let Err(e) = match capture.open_file(&source, CAP_FFMPEG) {
+                if let Err(e) = match capture.open_file(&source, self.api_preference) {
                     Ok(state) if state => Ok(()),
                     Ok(_) => match capture.is_opened() {
                         // todo: is this branch really necessary?
@@ -104,7 +120,33 @@ impl CameraHandler for OpenCVCamera {
             ref mut capture, ..
         } = self.state
         {
-            capture.release().unwrap()
+            capture.release().unwrap();
+            self.state = InternalState::Waiting;
         }
     }
 }
+
+fn frame_from_mat(mat: &Mat) -> Result<Frame, CameraState> {
+    // two channel mats are not necessarily yuyv, captures are converted to
+    // bgr by default and anything else is rejected
+    let format = match mat.typ() {
+        CV_8UC1 => PixelFormat::Gray8,
+        CV_8UC3 => PixelFormat::Bgr8,
+        typ => {
+            return Err(CameraState::Error(format!("Unsupported frame type: {typ}")));
+        }
+    };
+
+    let data = mat
+        .data_bytes()
+        .map_err(|e| CameraState::Error(format!("Failed to access frame data: {e}")))?;
+    let stride = data.len() / mat.rows() as usize;
+
+    Ok(Frame::new(
+        data.to_vec(),
+        mat.cols() as u32,
+        mat.rows() as u32,
+        stride as u32,
+        format,
+    ))
+}
@@ -0,0 +1,389 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use log::{debug, trace, warn};
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use super::{DeviceInfo, EspFlasher, FlashError, FlashProgress, Link};
+use crate::CameraState;
+
+// Command protocol, one JSON document per line in both directions:
+// request:  {"commands":[{"command":"set_mdns","data":{"hostname":"left"}}]}
+// response: {"command":"set_mdns","status":"ok","data":...}
+//           {"command":"set_mdns","status":"error","error":"..."}
+// Responses are interleaved with frame packets and log lines.
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(2);
+// firmware predating the device info query never answers it, connecting
+// should not wait the full command timeout for those
+const PROBE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Streaming mode of the firmware
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamingMode {
+    /// Frames are sent over the serial port
+    Serial,
+    /// Frames are served as MJPEG stream over WiFi
+    Wifi,
+    /// The device shows up as USB webcam
+    Uvc,
+}
+
+impl StreamingMode {
+    fn as_str(&self) -> &'static str {
+        match self {
+            StreamingMode::Serial => "serial",
+            StreamingMode::Wifi => "wifi",
+            StreamingMode::Uvc => "uvc",
+        }
+    }
+
+    pub(crate) fn parse(mode: &str) -> Option<StreamingMode> {
+        match mode {
+            "serial" => Some(StreamingMode::Serial),
+            "wifi" => Some(StreamingMode::Wifi),
+            "uvc" => Some(StreamingMode::Uvc),
+            _ => None,
+        }
+    }
+}
+
+/// A command understood by the OpenIris firmware
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpenIrisCommand {
+    /// Checks whether the firmware is responsive
+    Ping,
+    /// Queries the firmware version and capabilities, see [`DeviceInfo`]
+    GetDeviceInfo,
+    /// Stores WiFi credentials, `name` identifies the network on the device
+    SetWifi {
+        name: String,
+        ssid: String,
+        password: String,
+        channel: u8,
+    },
+    /// Sets the mDNS hostname, e.g. `openiristracker` for
+    /// `openiristracker.local`
+    SetMdns { hostname: String },
+    /// Connects to the stored WiFi network
+    ConnectWifi,
+    /// Queries the WiFi connection, see [`super::WifiStatus`]
+    GetWifiStatus,
+    /// Switches the streaming mode, applied after a restart
+    SwitchMode(StreamingMode),
+    /// Restarts the device, the connection drops right after the response
+    RestartDevice,
+}
+
+impl OpenIrisCommand {
+    /// Returns the name of the command on the wire
+    pub fn name(&self) -> &'static str {
+        match self {
+            OpenIrisCommand::Ping => "ping",
+            OpenIrisCommand::GetDeviceInfo => "get_device_info",
+            OpenIrisCommand::SetWifi { .. } => "set_wifi",
+            OpenIrisCommand::SetMdns { .. } => "set_mdns",
+            OpenIrisCommand::ConnectWifi => "connect_wifi",
+            OpenIrisCommand::GetWifiStatus => "get_wifi_status",
+            OpenIrisCommand::SwitchMode(_) => "switch_mode",
+            OpenIrisCommand::RestartDevice => "restart_device",
+        }
+    }
+
+    fn data(&self) -> Option<Value> {
+        match self {
+            OpenIrisCommand::Ping
+            | OpenIrisCommand::GetDeviceInfo
+            | OpenIrisCommand::ConnectWifi
+            | OpenIrisCommand::GetWifiStatus
+            | OpenIrisCommand::RestartDevice => None,
+            OpenIrisCommand::SetWifi {
+                name,
+                ssid,
+                password,
+                channel,
+            } => Some(json!({
+                "name": name,
+                "ssid": ssid,
+                "password": password,
+                "channel": channel,
+            })),
+            OpenIrisCommand::SetMdns { hostname } => Some(json!({ "hostname": hostname })),
+            OpenIrisCommand::SwitchMode(mode) => Some(json!({ "mode": mode.as_str() })),
+        }
+    }
+
+    /// Serializes the command into a single line, without line ending
+    pub(crate) fn encode(&self) -> String {
+        let mut command = json!({ "command": self.name() });
+        if let Some(data) = self.data() {
+            command["data"] = data;
+        }
+
+        json!({ "commands": [command] }).to_string()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Ok,
+    Error,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    command: String,
+    status: Status,
+    #[serde(default)]
+    data: Value,
+    error: Option<String>,
+}
+
+/// Parses a response line
+/// - Returns `None` if the line is no response to the given command
+/// - Errors carry the message reported by the device
+fn parse_response(line: &str, command: &str) -> Option<Result<Value, String>> {
+    let response: Response = match serde_json::from_str(line) {
+        Ok(response) => response,
+        Err(e) => {
+            trace!("ignoring malformed response {line}: {e}");
+            return None;
+        }
+    };
+    if response.command != command {
+        trace!("ignoring response to {}", response.command);
+        return None;
+    }
+
+    Some(match response.status {
+        Status::Ok => Ok(response.data),
+        Status::Error => Err(response.error.unwrap_or_default()),
+    })
+}
+
+/// Sends commands to an OpenIris device, see
+/// [`super::OpenIrisCamera::commander`]
+/// - Frame capture continues while waiting for a response, frames read in the
+///   meantime are kept for the capture thread
+/// - Commands from clones of the same commander are sent one at a time
+#[derive(Debug, Clone)]
+pub struct OpenIrisCommander {
+    link: Arc<Link>,
+    timeout: Duration,
+}
+
+impl OpenIrisCommander {
+    pub(super) fn new(link: Arc<Link>) -> Self {
+        Self {
+            link,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Overrides how long to wait for a response
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sends the command and waits for its response
+    /// - Returns the `data` of the response, `Value::Null` if there is none
+    /// - Returns [`CameraState::Timeout`] if the device did not respond in
+    ///   time and [`CameraState::Disconnected`] if the camera is not connected
+    /// - Returns [`CameraState::Error`] if the device reported an error
+    pub fn send(&self, command: &OpenIrisCommand) -> Result<Value, CameraState> {
+        self.request(command)?.map_err(|error| {
+            warn!("command {} failed: {error}", command.name());
+            CameraState::Error(format!("{} failed: {error}", command.name()))
+        })
+    }
+
+    /// Like [`Self::send`] but keeps the error reported by the device apart
+    /// from link errors
+    pub(crate) fn request(
+        &self,
+        command: &OpenIrisCommand,
+    ) -> Result<Result<Value, String>, CameraState> {
+        let _sending = self.link.command.lock().expect("command lock poisoned");
+        let deadline = Instant::now() + self.timeout;
+
+        {
+            let mut io = self.link.io();
+            // responses left over from commands that timed out
+            io.lines.clear();
+            debug!("sending command {}", command.name());
+            io.write_line(&command.encode())?;
+        }
+
+        loop {
+            // released between reads, letting frame capture through
+            let mut io = self.link.io();
+            while let Some(line) = io.lines.pop_front() {
+                if let Some(result) = parse_response(&line, command.name()) {
+                    return Ok(result);
+                }
+            }
+
+            if Instant::now() >= deadline {
+                warn!("no response to command {}", command.name());
+                return Err(CameraState::Timeout);
+            }
+
+            match io.pump() {
+                Ok(()) | Err(CameraState::Timeout) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Checks whether the firmware is responsive
+    pub fn ping(&self) -> Result<(), CameraState> {
+        self.send(&OpenIrisCommand::Ping).map(|_| ())
+    }
+
+    /// Returns the device info queried after connecting
+    /// - `None` until connected or if the firmware did not report it
+    pub fn device_info(&self) -> Option<DeviceInfo> {
+        self.link.io().info.clone()
+    }
+
+    /// Queries the device info, updating the one returned by
+    /// [`Self::device_info`]
+    pub fn query_device_info(&self) -> Result<DeviceInfo, CameraState> {
+        let data = self.send(&OpenIrisCommand::GetDeviceInfo)?;
+        self.store_device_info(&data)
+    }
+
+    /// Queries the device info while connecting, with a shorter timeout
+    /// - Returns `Ok(None)` for firmware predating the query, it either
+    ///   ignores the query or rejects it as unknown command
+    pub(crate) fn probe_device_info(&self) -> Result<Option<DeviceInfo>, CameraState> {
+        let command = OpenIrisCommand::GetDeviceInfo;
+        let probe = self.clone().with_timeout(PROBE_TIMEOUT.min(self.timeout));
+
+        match probe.request(&command) {
+            Ok(Ok(data)) => self.store_device_info(&data).map(Some),
+            Ok(Err(error)) if is_unknown_command(&error) => Ok(None),
+            Ok(Err(error)) => {
+                warn!("command {} failed: {error}", command.name());
+                Err(CameraState::Error(format!(
+                    "{} failed: {error}",
+                    command.name()
+                )))
+            }
+            Err(CameraState::Timeout) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn store_device_info(&self, data: &Value) -> Result<DeviceInfo, CameraState> {
+        let info = DeviceInfo::parse(data).ok_or_else(|| {
+            warn!("malformed device info: {data}");
+            CameraState::Error("malformed device info".to_string())
+        })?;
+
+        self.link.io().info = Some(info.clone());
+        Ok(info)
+    }
+
+    /// Writes a firmware image to the device, see [`EspFlasher`]
+    /// - Frame capture and commands are blocked until the device has
+    ///   restarted
+    pub fn flash(
+        &self,
+        flasher: &EspFlasher,
+        image: &[u8],
+        progress: impl FnMut(FlashProgress),
+    ) -> Result<(), FlashError> {
+        let _sending = self.link.command.lock().expect("command lock poisoned");
+        let mut io = self.link.io();
+        let mut port = io
+            .port
+            .take()
+            .ok_or(FlashError::Link(CameraState::Disconnected))?;
+
+        let result = flasher.flash_port(port.as_mut(), image, progress);
+        // starts over with the restarted firmware
+        io.attach(port);
+        result
+    }
+}
+
+/// Whether the device rejected a command because its firmware predates it
+pub(crate) fn is_unknown_command(error: &str) -> bool {
+    error.to_lowercase().contains("unknown command")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::fake::FakeDevice, *};
+    use crate::{CameraHandler, OpenIrisCamera};
+
+    #[test]
+    fn test_encode() {
+        let command = OpenIrisCommand::SetMdns {
+            hostname: "left-eye".to_string(),
+        };
+        assert_eq!(
+            command.encode(),
+            r#"{"commands":[{"command":"set_mdns","data":{"hostname":"left-eye"}}]}"#
+        );
+        assert_eq!(
+            OpenIrisCommand::Ping.encode(),
+            r#"{"commands":[{"command":"ping"}]}"#
+        );
+    }
+
+    #[test]
+    fn test_commands_while_streaming() {
+        let device = FakeDevice::new(|command| match command["command"].as_str()? {
+            "ping" => Some(json!({ "command": "ping", "status": "ok", "data": "pong" })),
+            "set_mdns" => Some(json!({
+                "command": "set_mdns",
+                "status": "error",
+                "error": "hostname too long",
+            })),
+            _ => None,
+        });
+        device.set_streaming(true);
+
+        let mut camera = OpenIrisCamera::init();
+        camera.link.io().attach(Box::new(device.clone()));
+        let commander = camera.commander().with_timeout(Duration::from_millis(200));
+
+        let capture = std::thread::spawn(move || {
+            for _ in 0..50 {
+                let frame = camera.get_frame().expect("frames should keep flowing");
+                assert_eq!((frame.width, frame.height), (8, 8));
+            }
+        });
+
+        assert_eq!(commander.send(&OpenIrisCommand::Ping), Ok(json!("pong")));
+        let error = commander.send(&OpenIrisCommand::SetMdns {
+            hostname: "a".repeat(64),
+        });
+        assert!(matches!(error, Err(CameraState::Error(_))));
+        assert_eq!(
+            commander.send(&OpenIrisCommand::RestartDevice),
+            Err(CameraState::Timeout)
+        );
+
+        capture.join().expect("capture thread should not panic");
+        let received: Vec<_> = device
+            .commands()
+            .iter()
+            .map(|command| command["command"].clone())
+            .collect();
+        assert_eq!(received, ["ping", "set_mdns", "restart_device"]);
+    }
+
+    #[test]
+    fn test_disconnected() {
+        let commander = OpenIrisCamera::init().commander();
+        assert_eq!(commander.ping(), Err(CameraState::Disconnected));
+    }
+}
@@ -0,0 +1,243 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use log::{debug, info, warn};
+use serialport::{ClearBuffer, DataBits, FlowControl, SerialPort};
+
+use super::{BAUD_RATE, SerialPortVariant, discovery, flash};
+use crate::{CameraSource, CameraState};
+
+// rates tried by `BaudRate::Auto`, fastest first
+const AUTO_BAUD_RATES: &[u32] = &[3_000_000, 2_000_000, 921_600, 460_800, 230_400, 115_200];
+// long enough for a couple of frames even at the lowest rate
+const AUTO_BAUD_PROBE: Duration = Duration::from_millis(500);
+
+/// Opens the port for a location passed to [`crate::CameraHandler::connect`],
+/// e.g. to reach the device through a network bridge or a virtual port
+/// - The config is applied by the opener, resetting and baud rate detection
+///   happen afterwards
+pub type PortOpener =
+    Arc<dyn Fn(&str, &SerialConfig) -> Result<Box<dyn SerialPort>, CameraState> + Send + Sync>;
+
+/// Baud rate of the serial connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaudRate {
+    Fixed(u32),
+    /// Tries common rates from 3 Mbaud down to 115200 until OpenIris frames
+    /// show up
+    Auto,
+}
+
+impl FromStr for BaudRate {
+    type Err = ();
+
+    /// Parses `auto` or a number, e.g. `921600`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(BaudRate::Auto),
+            rate => rate.parse().map(BaudRate::Fixed).map_err(|_| ()),
+        }
+    }
+}
+
+/// Serial port settings of an OpenIris connection
+/// - Defaults to 3 Mbaud, 115200 baud on macOS, without flow control
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerialConfig {
+    pub baud_rate: BaudRate,
+    /// How long a read may block before giving up
+    pub timeout: Duration,
+    pub flow_control: FlowControl,
+    pub data_bits: DataBits,
+    /// Restarts the device through DTR/RTS after opening the port, for
+    /// bridges that do not do so on their own
+    pub reset: bool,
+}
+
+impl Default for SerialConfig {
+    fn default() -> Self {
+        Self {
+            baud_rate: BaudRate::Fixed(BAUD_RATE),
+            timeout: Duration::from_millis(100),
+            flow_control: FlowControl::None,
+            data_bits: DataBits::Eight,
+            reset: false,
+        }
+    }
+}
+
+impl SerialConfig {
+    /// Reads the settings from the query of the source, unset ones keep their
+    /// defaults
+    /// - `baud`: rate or `auto`, e.g. `serial://COM13?baud=921600`
+    /// - `timeout`: read timeout in milliseconds
+    /// - `flow`: `none`, `software` or `hardware`
+    /// - `data_bits`: `5` to `8`
+    /// - `reset`: `true` to restart the device after opening the port
+    ///
+    /// Returns [`CameraState::InvalidSource`] for malformed values.
+    pub fn from_source(source: &CameraSource) -> Result<SerialConfig, CameraState> {
+        let mut config = SerialConfig::default();
+
+        if let Some(baud_rate) = source.parse_param("baud")? {
+            config.baud_rate = baud_rate;
+        }
+        if let Some(timeout) = source.parse_param("timeout")? {
+            config.timeout = Duration::from_millis(timeout);
+        }
+        if let Some(flow_control) = source.param("flow") {
+            config.flow_control = match flow_control {
+                "none" => FlowControl::None,
+                "software" => FlowControl::Software,
+                "hardware" => FlowControl::Hardware,
+                _ => {
+                    warn!("invalid flow control in {source}: {flow_control}");
+                    return Err(CameraState::InvalidSource);
+                }
+            };
+        }
+        if let Some(data_bits) = source.parse_param::<u8>("data_bits")? {
+            config.data_bits = match data_bits {
+                5 => DataBits::Five,
+                6 => DataBits::Six,
+                7 => DataBits::Seven,
+                8 => DataBits::Eight,
+                _ => {
+                    warn!("invalid data bits in {source}: {data_bits}");
+                    return Err(CameraState::InvalidSource);
+                }
+            };
+        }
+        if let Some(reset) = source.parse_param("reset")? {
+            config.reset = reset;
+        }
+
+        Ok(config)
+    }
+
+    /// Returns the rate to open the port with
+    pub fn initial_baud_rate(&self) -> u32 {
+        match self.baud_rate {
+            BaudRate::Fixed(baud_rate) => baud_rate,
+            BaudRate::Auto => AUTO_BAUD_RATES[0],
+        }
+    }
+
+    /// Opens the port, detecting the baud rate if needed
+    /// - Uses the opener if given, the system serial port otherwise
+    pub(crate) fn open(
+        &self,
+        path: &str,
+        opener: Option<&PortOpener>,
+    ) -> Result<SerialPortVariant, CameraState> {
+        let mut port = match opener {
+            Some(open) => open(path, self)?,
+            None => serialport::new(path, self.initial_baud_rate())
+                .timeout(self.timeout)
+                .flow_control(self.flow_control)
+                .data_bits(self.data_bits)
+                .open()
+                .map_err(|e| CameraState::Error(format!("Failed to open port: {e}")))?,
+        };
+        self.prepare(&mut port)?;
+
+        Ok(port)
+    }
+
+    /// Resets the device and detects the baud rate of a freshly opened port
+    fn prepare(&self, port: &mut SerialPortVariant) -> Result<(), CameraState> {
+        if self.reset {
+            reset(port)?;
+        }
+
+        if self.baud_rate == BaudRate::Auto {
+            let Some(baud_rate) = detect_baud_rate(port, AUTO_BAUD_RATES, AUTO_BAUD_PROBE) else {
+                warn!("no OpenIris frames at any baud rate");
+                return Err(CameraState::Error("Failed to detect baud rate".to_string()));
+            };
+            info!("detected baud rate {baud_rate}");
+        }
+
+        Ok(())
+    }
+}
+
+/// Restarts the device by pulling EN low through RTS, keeping IO0 high so it
+/// boots the firmware
+fn reset(port: &mut SerialPortVariant) -> Result<(), CameraState> {
+    debug!("resetting device");
+    flash::set_control_lines(port.as_mut(), flash::HARD_RESET)
+        .map_err(|e| CameraState::Error(format!("Failed to reset device: {e}")))
+}
+
+/// Switches through the candidates until the OpenIris frame header shows up
+/// - Leaves the port at the detected rate
+fn detect_baud_rate(
+    port: &mut SerialPortVariant,
+    candidates: &[u32],
+    timeout: Duration,
+) -> Option<u32> {
+    candidates.iter().copied().find(|&baud_rate| {
+        if let Err(e) = port.set_baud_rate(baud_rate) {
+            debug!("failed to set baud rate {baud_rate}: {e}");
+            return false;
+        }
+        // bytes received at the previous rate are garbage
+        let _ = port.clear(ClearBuffer::Input);
+
+        let found = discovery::probe(port, timeout);
+        debug!("probed baud rate {baud_rate}: {found}");
+        found
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::fake::FakeDevice, *};
+
+    #[test]
+    fn test_from_source() {
+        let source = CameraSource::parse(
+            "serial:///dev/ttyUSB0?baud=921600&timeout=250&flow=hardware&data_bits=7&reset=true",
+        )
+        .unwrap();
+        let config = SerialConfig::from_source(&source).expect("config should parse");
+        assert_eq!(
+            config,
+            SerialConfig {
+                baud_rate: BaudRate::Fixed(921_600),
+                timeout: Duration::from_millis(250),
+                flow_control: FlowControl::Hardware,
+                data_bits: DataBits::Seven,
+                reset: true,
+            }
+        );
+
+        let source = CameraSource::parse("serial://COM13?baud=auto").unwrap();
+        let config = SerialConfig::from_source(&source).unwrap();
+        assert_eq!(config.baud_rate, BaudRate::Auto);
+        assert_eq!(config.timeout, SerialConfig::default().timeout);
+
+        for invalid in ["baud=fast", "data_bits=9", "flow=rts"] {
+            let source = CameraSource::parse(&format!("serial://COM13?{invalid}")).unwrap();
+            assert_eq!(
+                SerialConfig::from_source(&source),
+                Err(CameraState::InvalidSource)
+            );
+        }
+    }
+
+    #[test]
+    fn test_detect_baud_rate() {
+        let device = FakeDevice::new(|_| None).with_baud_rate(460_800);
+        device.set_streaming(true);
+
+        let mut port: SerialPortVariant = Box::new(device.clone());
+        let detected = detect_baud_rate(&mut port, AUTO_BAUD_RATES, Duration::from_millis(100));
+        assert_eq!(detected, Some(460_800));
+        assert_eq!(port.baud_rate().unwrap(), 460_800);
+
+        let detected =
+            detect_baud_rate(&mut port, &[3_000_000, 115_200], Duration::from_millis(50));
+        assert_eq!(detected, None);
+    }
+}
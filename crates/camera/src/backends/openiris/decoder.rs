@@ -0,0 +1,448 @@
+use std::collections::VecDeque;
+
+use log::{trace, warn};
+use nom::{IResult, Parser, bytes, number, sequence};
+
+// Serial communication protocol:
+// header-begin (2 bytes)
+// header-type (2 bytes)
+// packet-size (2 bytes)
+// packet (packet-size bytes)
+// https://github.com/EyeTrackVR/OpenIris/blob/5da262c8daf27ea2cb060ec2e41a19a1c1c3db29/ESP/lib/src/io/Serial/SerialManager.cpp#L31
+
+// header + header type
+pub(crate) const ETVR_HEADER_FRAME: &[u8] = &[0xFF, 0xA0, 0xFF, 0xA1];
+const MARKER_LEN: usize = ETVR_HEADER_FRAME.len();
+const LENGTH_LEN: usize = 2;
+const HEADER_LEN: usize = MARKER_LEN + LENGTH_LEN;
+// a full packet is at most u16::MAX bytes, anything beyond a few of them is
+// stale data that only adds latency
+const MAX_BUFFERED: usize = 4 * (HEADER_LEN + u16::MAX as usize);
+
+// firmware log and command responses are line based, anything longer is
+// binary garbage
+const MAX_LINE_LEN: usize = 4096;
+const MAX_QUEUED_LINES: usize = 64;
+
+/// Outcome of scanning the ring buffer once
+enum Scan {
+    /// A complete packet preceded by `skip` bytes of text or garbage
+    Packet { skip: usize, len: usize },
+    /// Text or garbage
+    Skip(usize),
+    /// A packet cut short by the next header
+    Truncated(usize),
+    /// More data is needed, the first `skip` bytes are text or garbage
+    Wait { skip: usize },
+}
+
+/// A unit of data received from the OpenIris firmware
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OpenIrisMessage {
+    /// Payload of a frame packet, usually a JPEG image
+    Packet(Vec<u8>),
+    /// A line of text between packets, e.g. a log line or a command response
+    /// without its line ending
+    Line(String),
+}
+
+/// Stateful decoder for the OpenIris serial stream
+/// - Bytes are fed in arbitrary chunks, partial headers and packets are kept
+///   in a ring buffer until the rest arrives
+/// - Garbage between packets is skipped by resynchronising on
+///   `ETVR_HEADER_FRAME`, text lines in between are kept
+/// - A packet that is cut short by the next header is dropped, decoding
+///   resumes at that header
+/// - A payload ending like the start of a header is held back until the
+///   following bytes tell both apart, jpeg payloads never do as they end with
+///   `0xFFD9`
+#[derive(Debug, Default)]
+pub struct OpenIrisDecoder {
+    buf: VecDeque<u8>,
+    dropped_bytes: u64,
+    line: Vec<u8>,
+    line_overflow: bool,
+    lines: VecDeque<String>,
+}
+
+impl OpenIrisDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends received bytes to the ring buffer
+    pub fn feed(&mut self, data: &[u8]) {
+        self.buf.extend(data);
+
+        if self.buf.len() > MAX_BUFFERED {
+            let excess = self.buf.len() - MAX_BUFFERED;
+            trace!("ring buffer full, dropping {excess} stale bytes");
+            self.discard(excess);
+        }
+    }
+
+    /// Returns the payload of the next complete packet
+    /// - Returns `None` if more data is needed
+    /// - Text lines in between are discarded
+    pub fn next_packet(&mut self) -> Option<Vec<u8>> {
+        loop {
+            match self.next_message()? {
+                OpenIrisMessage::Packet(packet) => return Some(packet),
+                OpenIrisMessage::Line(line) => trace!("skipping line: {line}"),
+            }
+        }
+    }
+
+    /// Returns the next complete packet or text line in order of arrival
+    /// - Returns `None` if more data is needed
+    pub fn next_message(&mut self) -> Option<OpenIrisMessage> {
+        loop {
+            if let Some(line) = self.lines.pop_front() {
+                return Some(OpenIrisMessage::Line(line));
+            }
+
+            match self.scan() {
+                Scan::Packet { skip: 0, len } => {
+                    let packet = self.buf.drain(..HEADER_LEN + len).skip(HEADER_LEN);
+                    return Some(OpenIrisMessage::Packet(packet.collect()));
+                }
+                // lines in front of the packet are returned first
+                Scan::Packet { skip, .. } | Scan::Skip(skip) => self.skip_text(skip),
+                Scan::Truncated(count) => self.discard(count),
+                Scan::Wait { skip } => {
+                    self.skip_text(skip);
+                    if self.lines.is_empty() {
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+
+    fn scan(&mut self) -> Scan {
+        let data = self.buf.make_contiguous();
+
+        let (rest, len) = match parse_next_header(data) {
+            Ok(parsed) => parsed,
+            Err(nom::Err::Incomplete(_)) => {
+                // no complete header yet, keep what could be the start of one
+                let keep = find_header(data)
+                    .map_or_else(|| partial_header_len(data), |start| data.len() - start);
+                return Scan::Wait {
+                    skip: data.len() - keep,
+                };
+            }
+            Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                warn!("failed to parse serial stream: {:?}", e.code);
+                return Scan::Skip(1);
+            }
+        };
+
+        let header_start = data.len() - rest.len() - HEADER_LEN;
+        let len = len as usize;
+        // the length field itself may already belong to the next header if
+        // the previous packet was cut short, so search right after the marker
+        let body = &data[header_start + MARKER_LEN..];
+        let end = LENGTH_LEN + len;
+        let window = &body[..(end + MARKER_LEN - 1).min(body.len())];
+
+        // a header starting inside the packet means it was cut short
+        if let Some(offset) = find_header(window).filter(|&offset| offset < end) {
+            warn!(
+                "dropping truncated packet, expected {len} bytes, got {}",
+                offset.saturating_sub(LENGTH_LEN)
+            );
+            return Scan::Truncated(header_start + MARKER_LEN + offset);
+        }
+
+        if body.len() < end {
+            // wait for the rest of the packet, keeping its header
+            return Scan::Wait { skip: header_start };
+        }
+
+        // the payload might end in the middle of the next header, which can
+        // only be told once a few more bytes arrived
+        if window.len() < end + MARKER_LEN - 1 && window.len() - partial_header_len(window) < end {
+            return Scan::Wait { skip: header_start };
+        }
+
+        Scan::Packet {
+            skip: header_start,
+            len,
+        }
+    }
+
+    /// Amount of bytes currently held in the ring buffer
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Amount of bytes outside of intact packets so far, e.g. text, garbage
+    /// or truncated packets
+    pub fn dropped_bytes(&self) -> u64 {
+        self.dropped_bytes
+    }
+
+    /// Drops all buffered bytes and lines, e.g. after reconnecting
+    pub fn clear(&mut self) {
+        self.discard(self.buf.len());
+        self.line.clear();
+        self.line_overflow = false;
+        self.lines.clear();
+    }
+
+    /// Discards bytes outside of packets while collecting text lines
+    fn skip_text(&mut self, count: usize) {
+        for byte in self.buf.drain(..count) {
+            match byte {
+                b'\n' => {
+                    let line = std::mem::take(&mut self.line);
+                    if std::mem::take(&mut self.line_overflow) {
+                        continue;
+                    }
+                    let Ok(line) = String::from_utf8(line) else {
+                        continue;
+                    };
+                    let line = line.trim_end_matches('\r');
+                    if line.is_empty() {
+                        continue;
+                    }
+
+                    if self.lines.len() == MAX_QUEUED_LINES {
+                        self.lines.pop_front();
+                    }
+                    self.lines.push_back(line.to_string());
+                }
+                _ if self.line.len() < MAX_LINE_LEN => self.line.push(byte),
+                _ => self.line_overflow = true,
+            }
+        }
+        self.dropped_bytes += count as u64;
+    }
+
+    fn discard(&mut self, count: usize) {
+        if count > 0 {
+            self.buf.drain(..count);
+            self.dropped_bytes += count as u64;
+        }
+    }
+}
+
+pub(crate) fn find_header(data: &[u8]) -> Option<usize> {
+    data.windows(MARKER_LEN)
+        .position(|window| window == ETVR_HEADER_FRAME)
+}
+
+/// Length of the longest header prefix the data ends with
+fn partial_header_len(data: &[u8]) -> usize {
+    (1..MARKER_LEN)
+        .rev()
+        .find(|&len| data.ends_with(&ETVR_HEADER_FRAME[..len]))
+        .unwrap_or(0)
+}
+
+pub(crate) fn parse_next_header(input: &[u8]) -> IResult<&[u8], u16> {
+    let (input, _preamble) = bytes::streaming::take_until(ETVR_HEADER_FRAME).parse(input)?;
+
+    sequence::preceded(
+        bytes::streaming::tag(ETVR_HEADER_FRAME),
+        number::streaming::le_u16,
+    )
+    .parse(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn packet(payload: &[u8]) -> Vec<u8> {
+        let len = (payload.len() as u16).to_le_bytes();
+        [ETVR_HEADER_FRAME, &len, payload].concat()
+    }
+
+    fn decode_all(decoder: &mut OpenIrisDecoder) -> Vec<Vec<u8>> {
+        std::iter::from_fn(|| decoder.next_packet()).collect()
+    }
+
+    #[test]
+    fn test_byte_by_byte() {
+        let stream = [packet(b"first"), packet(b"second")].concat();
+        let mut decoder = OpenIrisDecoder::new();
+
+        let mut packets = Vec::new();
+        for byte in stream {
+            decoder.feed(&[byte]);
+            packets.extend(decode_all(&mut decoder));
+        }
+
+        assert_eq!(packets, [b"first".to_vec(), b"second".to_vec()]);
+        assert_eq!(decoder.buffered(), 0);
+        assert_eq!(decoder.dropped_bytes(), 0);
+    }
+
+    #[test]
+    fn test_header_split_across_reads() {
+        let stream = [b"noise".to_vec(), packet(b"payload")].concat();
+        let mut decoder = OpenIrisDecoder::new();
+
+        // split right in the middle of the header
+        decoder.feed(&stream[..7]);
+        assert_eq!(decoder.next_packet(), None);
+        decoder.feed(&stream[7..]);
+
+        assert_eq!(decoder.next_packet(), Some(b"payload".to_vec()));
+        assert_eq!(decoder.dropped_bytes(), 5);
+    }
+
+    #[test]
+    fn test_resync_after_truncated_packet() {
+        let mut truncated = packet(b"this packet is cut short");
+        truncated.truncate(12);
+        let stream = [truncated, packet(b"intact")].concat();
+
+        let mut decoder = OpenIrisDecoder::new();
+        decoder.feed(&stream);
+
+        assert_eq!(decode_all(&mut decoder), [b"intact".to_vec()]);
+    }
+
+    #[test]
+    fn test_lines_between_packets() {
+        let stream = [
+            b"I (312) cpu_start: Starting scheduler\r\n".to_vec(),
+            packet(b"first"),
+            b"{\"command\":\"ping\",".to_vec(),
+            packet(b"second"),
+            b"\"status\":\"ok\"}\n".to_vec(),
+        ]
+        .concat();
+
+        let mut decoder = OpenIrisDecoder::new();
+        decoder.feed(&stream);
+
+        let messages: Vec<_> = std::iter::from_fn(|| decoder.next_message()).collect();
+        assert_eq!(
+            messages,
+            [
+                OpenIrisMessage::Line("I (312) cpu_start: Starting scheduler".to_string()),
+                OpenIrisMessage::Packet(b"first".to_vec()),
+                OpenIrisMessage::Packet(b"second".to_vec()),
+                // packets may interrupt a line
+                OpenIrisMessage::Line(r#"{"command":"ping","status":"ok"}"#.to_string()),
+            ]
+        );
+    }
+
+    #[derive(Debug, Clone)]
+    enum Segment {
+        Packet(Vec<u8>),
+        Garbage(Vec<u8>),
+        Truncated { payload: Vec<u8>, cut: usize },
+        BogusLength { payload: Vec<u8>, len: u16 },
+    }
+
+    impl Segment {
+        fn encode(&self) -> Vec<u8> {
+            match self {
+                Segment::Packet(payload) => packet(payload),
+                Segment::Garbage(garbage) => garbage.clone(),
+                Segment::Truncated { payload, cut } => {
+                    let mut data = packet(payload);
+                    data.truncate(cut % data.len());
+                    data
+                }
+                Segment::BogusLength { payload, len } => {
+                    [ETVR_HEADER_FRAME, &len.to_le_bytes(), payload].concat()
+                }
+            }
+        }
+    }
+
+    // real jpeg data never contains the header marker, without 0xA1 bytes
+    // neither payloads nor garbage can form one by accident, while plenty of
+    // 0xFF and 0xA0 bytes still form partial ones
+    fn bytes(max_len: usize) -> impl Strategy<Value = Vec<u8>> {
+        prop::collection::vec(any::<u8>(), 0..max_len).prop_map(|mut data| {
+            for byte in &mut data {
+                *byte = match *byte {
+                    0xA1 => 0xA2,
+                    0x00..0x20 => 0xFF,
+                    0x20..0x30 => 0xA0,
+                    byte => byte,
+                };
+            }
+            data
+        })
+    }
+
+    fn segment() -> impl Strategy<Value = Segment> {
+        prop_oneof![
+            4 => bytes(512).prop_map(Segment::Packet),
+            1 => bytes(64).prop_map(Segment::Garbage),
+            1 => (bytes(512), any::<usize>())
+                .prop_map(|(payload, cut)| Segment::Truncated { payload, cut }),
+            1 => (bytes(512), any::<u16>())
+                .prop_map(|(payload, len)| Segment::BogusLength { payload, len }),
+        ]
+    }
+
+    fn decode_chunked(stream: &[u8], chunks: &[usize]) -> Vec<Vec<u8>> {
+        let mut decoder = OpenIrisDecoder::new();
+        let mut packets = Vec::new();
+
+        let mut rest = stream;
+        for chunk in chunks.iter().cycle() {
+            if rest.is_empty() {
+                break;
+            }
+            let (head, tail) = rest.split_at((*chunk).min(rest.len()));
+            decoder.feed(head);
+            packets.extend(decode_all(&mut decoder));
+            rest = tail;
+        }
+
+        packets
+    }
+
+    proptest! {
+        #[test]
+        fn prop_never_panics(
+            stream in prop::collection::vec(any::<u8>(), 0..4096),
+            chunks in prop::collection::vec(1..512usize, 1..16),
+        ) {
+            decode_chunked(&stream, &chunks);
+        }
+
+        #[test]
+        fn prop_recovers_intact_packets(
+            segments in prop::collection::vec(segment(), 0..32),
+            chunks in prop::collection::vec(1..1024usize, 1..16),
+        ) {
+            // the device keeps streaming, a payload that ends like the start
+            // of a header is only released once the following bytes arrived
+            let mut stream: Vec<u8> = segments.iter().flat_map(Segment::encode).collect();
+            stream.extend(packet(&[0xFF, 0xD9]));
+            let intact: Vec<&Vec<u8>> = segments
+                .iter()
+                .filter_map(|segment| match segment {
+                    Segment::Packet(payload) => Some(payload),
+                    _ => None,
+                })
+                .collect();
+
+            // broken segments may surface as bogus packets, but every intact
+            // packet has to come out in order
+            let packets = decode_chunked(&stream, &chunks);
+            let mut packets = packets.iter();
+            for payload in intact {
+                prop_assert!(
+                    packets.any(|packet| packet == payload),
+                    "lost intact packet of {} bytes",
+                    payload.len()
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,250 @@
+use std::{
+    io::{self, Read},
+    time::{Duration, Instant},
+};
+
+use log::{debug, trace};
+use serialport::{SerialPortInfo, SerialPortType, UsbPortInfo};
+
+use super::{BAUD_RATE, decoder};
+use crate::CameraState;
+
+/// USB to serial bridges found on OpenIris capable boards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UsbBridge {
+    /// Native USB Serial/JTAG of the ESP32-S3, e.g. XIAO ESP32S3
+    Esp32S3,
+    /// WCH CH340, common on ESP32-CAM programmer boards
+    Ch340,
+    /// WCH CH9102
+    Ch9102,
+    /// Silicon Labs CP210x
+    Cp210x,
+}
+
+const KNOWN_BRIDGES: &[(u16, u16, UsbBridge)] = &[
+    (0x303A, 0x1001, UsbBridge::Esp32S3),
+    (0x1A86, 0x7523, UsbBridge::Ch340),
+    (0x1A86, 0x55D4, UsbBridge::Ch9102),
+    (0x10C4, 0xEA60, UsbBridge::Cp210x),
+];
+
+impl UsbBridge {
+    /// Identifies the bridge by its USB vendor and product id
+    pub fn from_usb_id(vid: u16, pid: u16) -> Option<UsbBridge> {
+        KNOWN_BRIDGES
+            .iter()
+            .find(|(known_vid, known_pid, _)| (*known_vid, *known_pid) == (vid, pid))
+            .map(|(_, _, bridge)| *bridge)
+    }
+}
+
+/// A serial port that is likely connected to an OpenIris tracker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenIrisDevice {
+    /// Port name as passed to [`crate::Camera::connect`], e.g. `COM13`
+    pub port: String,
+    pub bridge: UsbBridge,
+    pub vid: u16,
+    pub pid: u16,
+    pub serial_number: Option<String>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    /// Whether OpenIris frames were seen on the port
+    /// - `None` if the port was not probed or could not be opened, e.g.
+    ///   because a camera is already connected to it
+    pub streaming: Option<bool>,
+}
+
+impl OpenIrisDevice {
+    /// Returns the source URI for [`crate::Camera::open`]
+    pub fn source(&self) -> String {
+        format!("serial://{}", self.port)
+    }
+
+    /// Returns the device if the port belongs to a known USB bridge
+    pub fn identify(info: &SerialPortInfo) -> Option<OpenIrisDevice> {
+        let SerialPortType::UsbPort(UsbPortInfo {
+            vid,
+            pid,
+            serial_number,
+            manufacturer,
+            product,
+            ..
+        }) = &info.port_type
+        else {
+            return None;
+        };
+
+        // macOS lists every device twice, the call-out device is the one to use
+        if info.port_name.starts_with("/dev/tty.") {
+            return None;
+        }
+
+        Some(OpenIrisDevice {
+            port: info.port_name.clone(),
+            bridge: UsbBridge::from_usb_id(*vid, *pid)?,
+            vid: *vid,
+            pid: *pid,
+            serial_number: serial_number.clone(),
+            manufacturer: manufacturer.clone(),
+            product: product.clone(),
+            streaming: None,
+        })
+    }
+}
+
+/// Enumerates serial ports that may be connected to OpenIris trackers
+/// - Ports are filtered by the USB ids of known bridges
+/// - Probing additionally listens on every candidate for the OpenIris frame
+///   header, which requires the port to be unused
+#[derive(Debug, Clone)]
+pub struct OpenIrisDiscovery {
+    probe_timeout: Option<Duration>,
+    baud_rate: u32,
+}
+
+impl Default for OpenIrisDiscovery {
+    fn default() -> Self {
+        Self {
+            probe_timeout: None,
+            baud_rate: BAUD_RATE,
+        }
+    }
+}
+
+impl OpenIrisDiscovery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Listens on every candidate for up to the given duration
+    pub fn with_probe(mut self, timeout: Duration) -> Self {
+        self.probe_timeout = Some(timeout);
+        self
+    }
+
+    /// Overrides the platform default baud rate used for probing
+    pub fn with_baud_rate(mut self, baud_rate: u32) -> Self {
+        self.baud_rate = baud_rate;
+        self
+    }
+
+    /// Returns all candidates, ordered by port name
+    pub fn scan(&self) -> Result<Vec<OpenIrisDevice>, CameraState> {
+        let ports = serialport::available_ports()
+            .map_err(|e| CameraState::Error(format!("Failed to list serial ports: {e}")))?;
+
+        let mut devices: Vec<_> = ports.iter().filter_map(OpenIrisDevice::identify).collect();
+        devices.sort_by(|a, b| a.port.cmp(&b.port));
+
+        if let Some(timeout) = self.probe_timeout {
+            for device in &mut devices {
+                device.streaming = self.probe_port(&device.port, timeout);
+            }
+        }
+
+        Ok(devices)
+    }
+
+    fn probe_port(&self, port: &str, timeout: Duration) -> Option<bool> {
+        let mut port = match serialport::new(port, self.baud_rate)
+            .timeout(Duration::from_millis(50))
+            .open()
+        {
+            Ok(port) => port,
+            Err(e) => {
+                debug!("failed to open {port} for probing: {e}");
+                return None;
+            }
+        };
+
+        Some(probe(&mut port, timeout))
+    }
+}
+
+/// Reads from the port until the OpenIris frame header shows up or the
+/// timeout elapsed
+pub(crate) fn probe<R: Read + ?Sized>(port: &mut R, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    let mut data = Vec::new();
+    let mut buf = [0u8; 1024];
+
+    while Instant::now() < deadline {
+        match port.read(&mut buf) {
+            Ok(0) => break,
+            Ok(read) => data.extend_from_slice(&buf[..read]),
+            Err(e) if e.kind() == io::ErrorKind::TimedOut => continue,
+            Err(e) => {
+                trace!("probing failed: {e}");
+                break;
+            }
+        }
+
+        if decoder::find_header(&data).is_some() {
+            return true;
+        }
+        // keep what could be the start of a header
+        let keep = data.len().min(decoder::ETVR_HEADER_FRAME.len() - 1);
+        data.drain(..data.len() - keep);
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usb_port(port_name: &str, vid: u16, pid: u16) -> SerialPortInfo {
+        SerialPortInfo {
+            port_name: port_name.to_string(),
+            port_type: SerialPortType::UsbPort(UsbPortInfo {
+                vid,
+                pid,
+                serial_number: Some("DC:DA:0C:12:34:56".to_string()),
+                manufacturer: Some("Espressif".to_string()),
+                product: Some("USB JTAG/serial debug unit".to_string()),
+            }),
+        }
+    }
+
+    #[test]
+    fn test_identify() {
+        let device = OpenIrisDevice::identify(&usb_port("COM13", 0x303A, 0x1001))
+            .expect("ESP32-S3 should be identified");
+        assert_eq!(device.bridge, UsbBridge::Esp32S3);
+        assert_eq!(device.serial_number.as_deref(), Some("DC:DA:0C:12:34:56"));
+        assert_eq!(device.source(), "serial://COM13");
+
+        let cp210x = OpenIrisDevice::identify(&usb_port("/dev/ttyUSB0", 0x10C4, 0xEA60));
+        assert_eq!(cp210x.map(|device| device.bridge), Some(UsbBridge::Cp210x));
+
+        // unknown usb device, duplicate macOS device and a non-usb port
+        assert_eq!(
+            OpenIrisDevice::identify(&usb_port("COM3", 0x046D, 0xC52B)),
+            None
+        );
+        assert_eq!(
+            OpenIrisDevice::identify(&usb_port("/dev/tty.usbmodem1101", 0x303A, 0x1001)),
+            None
+        );
+        let builtin = SerialPortInfo {
+            port_name: "/dev/ttyS0".to_string(),
+            port_type: SerialPortType::PciPort,
+        };
+        assert_eq!(OpenIrisDevice::identify(&builtin), None);
+    }
+
+    #[test]
+    fn test_probe() {
+        // boot log followed by a header split across two reads
+        let boot_log = b"ets Jul 29 2019 12:21:46\r\n\xFF\xA0".to_vec();
+        let frame = vec![0xFF, 0xA1, 0x02, 0x00, 0xFF, 0xD8];
+        let mut reader = io::Cursor::new(boot_log).chain(io::Cursor::new(frame));
+        assert!(probe(&mut reader, Duration::from_secs(1)));
+
+        let mut reader = io::Cursor::new(b"I (312) cpu_start: Starting scheduler".to_vec());
+        assert!(!probe(&mut reader, Duration::from_secs(1)));
+    }
+}
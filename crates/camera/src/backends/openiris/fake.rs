@@ -0,0 +1,400 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use serde_json::{Value, json};
+use serialport::{ClearBuffer, DataBits, FlowControl, Parity, SerialPort, StopBits};
+
+use super::{PortOpener, decoder::ETVR_HEADER_FRAME};
+use crate::CameraState;
+
+const GRAY_8X8: &[u8] = include_bytes!("../../../tests/data/gray_8x8.jpg");
+// serial drivers rarely hand out more than this in one go
+const MAX_READ: usize = 256;
+// start, 8 data and stop bit
+const BITS_PER_BYTE: f64 = 10.0;
+
+type Responder = Box<dyn FnMut(&Value) -> Option<Value> + Send>;
+
+struct Inner {
+    to_host: VecDeque<u8>,
+    from_host: Vec<u8>,
+    commands: Vec<Value>,
+    responder: Responder,
+    streaming: bool,
+    frames_sent: usize,
+    /// Rate the device transmits at, `None` for native USB where the rate of
+    /// the port does not matter
+    device_baud_rate: Option<u32>,
+    port_baud_rate: u32,
+    /// Delivers bytes no faster than the port baud rate allows
+    paced: bool,
+    /// Start of the current transmission and bytes delivered since
+    transmission: Option<(Instant, usize)>,
+    /// How long reads block without data
+    timeout: Duration,
+    unplugged: bool,
+    /// Current levels of DTR and RTS
+    lines: (bool, bool),
+    /// Every change of the control lines
+    line_changes: Vec<(bool, bool)>,
+}
+
+/// In-process stand-in for an OpenIris device on the other end of a serial
+/// port
+/// - Streams the same JPEG frame with a log line every few frames, scripted
+///   byte streams are played back before that
+/// - Command lines are answered by the responder, `None` leaves them
+///   unanswered
+/// - Clones share the device, so one can be handed out as port while the test
+///   keeps controlling it
+#[derive(Clone)]
+pub(crate) struct FakeDevice {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl FakeDevice {
+    pub fn new<F>(responder: F) -> Self
+    where
+        F: FnMut(&Value) -> Option<Value> + Send + 'static,
+    {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                to_host: VecDeque::new(),
+                from_host: Vec::new(),
+                commands: Vec::new(),
+                responder: Box::new(responder),
+                streaming: false,
+                frames_sent: 0,
+                device_baud_rate: None,
+                port_baud_rate: 3_000_000,
+                paced: false,
+                transmission: None,
+                timeout: Duration::from_millis(1),
+                unplugged: false,
+                lines: (false, false),
+                line_changes: Vec::new(),
+            })),
+        }
+    }
+
+    /// A device running current firmware, answering pings and device info
+    /// queries
+    pub fn tracker() -> Self {
+        Self::new(|command| {
+            let name = command["command"].as_str()?;
+            Some(match name {
+                "ping" => json!({ "command": name, "status": "ok" }),
+                "get_device_info" => json!({
+                    "command": name,
+                    "status": "ok",
+                    "data": {
+                        "version": "v5.1.0",
+                        "board": "xiao_esp32s3",
+                        "resolutions": ["8x8"],
+                        "mode": "serial",
+                    },
+                }),
+                _ => json!({ "command": name, "status": "error", "error": "Unknown command" }),
+            })
+        })
+    }
+
+    /// Paces reads to the baud rate of the port
+    pub fn with_timing(self) -> Self {
+        self.inner.lock().unwrap().paced = true;
+        self
+    }
+
+    /// Turns reads into line noise unless the port is set to the given rate
+    pub fn with_baud_rate(self, baud_rate: u32) -> Self {
+        self.inner.lock().unwrap().device_baud_rate = Some(baud_rate);
+        self
+    }
+
+    pub fn set_streaming(&self, streaming: bool) {
+        self.inner.lock().unwrap().streaming = streaming;
+    }
+
+    /// Queues bytes to be sent ahead of any streamed frames
+    pub fn play(&self, bytes: &[u8]) {
+        self.inner.lock().unwrap().to_host.extend(bytes);
+    }
+
+    /// Fails all reads and writes as if the USB cable was pulled
+    pub fn unplug(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.unplugged = true;
+        inner.to_host.clear();
+    }
+
+    pub fn replug(&self) {
+        self.inner.lock().unwrap().unplugged = false;
+    }
+
+    /// Returns an opener handing out this device, failing while unplugged
+    pub fn opener(&self) -> PortOpener {
+        let device = self.clone();
+        Arc::new(move |_, config| {
+            let mut port = device.clone();
+            if port.inner.lock().unwrap().unplugged {
+                return Err(CameraState::Error("Failed to open port".to_string()));
+            }
+            port.set_baud_rate(config.initial_baud_rate())
+                .and_then(|()| port.set_timeout(config.timeout))
+                .map_err(|e| CameraState::Error(e.to_string()))?;
+            Ok(Box::new(port))
+        })
+    }
+
+    /// Returns all commands received so far
+    pub fn commands(&self) -> Vec<Value> {
+        self.inner.lock().unwrap().commands.clone()
+    }
+
+    /// Returns the levels of DTR and RTS after each change
+    pub fn control_lines(&self) -> Vec<(bool, bool)> {
+        self.inner.lock().unwrap().line_changes.clone()
+    }
+
+    fn set_lines(&self, set: impl FnOnce(&mut (bool, bool))) {
+        let mut inner = self.inner.lock().unwrap();
+        let mut lines = inner.lines;
+        set(&mut lines);
+        if lines != inner.lines {
+            inner.lines = lines;
+            inner.line_changes.push(lines);
+        }
+    }
+}
+
+/// Builds an OpenIris packet around the payload
+pub(crate) fn packet(payload: &[u8]) -> Vec<u8> {
+    let mut packet = ETVR_HEADER_FRAME.to_vec();
+    packet.extend((payload.len() as u16).to_le_bytes());
+    packet.extend_from_slice(payload);
+    packet
+}
+
+impl Inner {
+    fn send_frame(&mut self) {
+        self.frames_sent += 1;
+        if self.frames_sent.is_multiple_of(3) {
+            let log = format!("I ({}) camera: frame sent\r\n", self.frames_sent);
+            self.to_host.extend(log.as_bytes());
+        }
+
+        self.to_host.extend(packet(GRAY_8X8));
+    }
+
+    /// Returns how many bytes went over the wire since the transmission
+    /// started
+    fn paced_len(&mut self) -> usize {
+        let (start, delivered) = *self.transmission.get_or_insert_with(|| (Instant::now(), 0));
+        let bytes_per_sec = self.port_baud_rate as f64 / BITS_PER_BYTE;
+        let transmitted = (start.elapsed().as_secs_f64() * bytes_per_sec) as usize;
+        transmitted.saturating_sub(delivered)
+    }
+
+    fn handle_lines(&mut self) {
+        while let Some(end) = self.from_host.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.from_host.drain(..=end).collect();
+            let Ok(request) = serde_json::from_slice::<Value>(&line) else {
+                continue;
+            };
+
+            for command in request["commands"].as_array().into_iter().flatten() {
+                self.commands.push(command.clone());
+                if let Some(response) = (self.responder)(command) {
+                    self.to_host.extend(response.to_string().as_bytes());
+                    self.to_host.push_back(b'\n');
+                }
+            }
+        }
+    }
+}
+
+impl Read for FakeDevice {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // a real port blocks until data arrives or the timeout elapsed
+        let deadline = Instant::now() + self.timeout();
+        loop {
+            match self.try_read(buf) {
+                Err(e) if e.kind() == io::ErrorKind::TimedOut && Instant::now() < deadline => {
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl FakeDevice {
+    fn try_read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.unplugged {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        if inner
+            .device_baud_rate
+            .is_some_and(|baud_rate| baud_rate != inner.port_baud_rate)
+        {
+            drop(inner);
+            std::thread::sleep(Duration::from_millis(1));
+            let len = buf.len().min(64);
+            buf[..len].fill(0x55);
+            return Ok(len);
+        }
+        if inner.to_host.is_empty() && inner.streaming {
+            inner.send_frame();
+        }
+        let mut len = buf.len().min(inner.to_host.len()).min(MAX_READ);
+        if inner.paced && len > 0 {
+            len = len.min(inner.paced_len());
+        }
+        if len == 0 {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+
+        for (byte, sent) in buf.iter_mut().zip(inner.to_host.drain(..len)) {
+            *byte = sent;
+        }
+        if let Some((_, delivered)) = inner.transmission.as_mut() {
+            *delivered += len;
+        }
+        // an idle line does not save up bytes for later
+        if inner.to_host.is_empty() {
+            inner.transmission = None;
+        }
+        Ok(len)
+    }
+}
+
+impl Write for FakeDevice {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.unplugged {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        inner.from_host.extend_from_slice(buf);
+        inner.handle_lines();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SerialPort for FakeDevice {
+    fn name(&self) -> Option<String> {
+        Some("fake".to_string())
+    }
+
+    fn baud_rate(&self) -> serialport::Result<u32> {
+        Ok(self.inner.lock().unwrap().port_baud_rate)
+    }
+
+    fn data_bits(&self) -> serialport::Result<DataBits> {
+        Ok(DataBits::Eight)
+    }
+
+    fn flow_control(&self) -> serialport::Result<FlowControl> {
+        Ok(FlowControl::None)
+    }
+
+    fn parity(&self) -> serialport::Result<Parity> {
+        Ok(Parity::None)
+    }
+
+    fn stop_bits(&self) -> serialport::Result<StopBits> {
+        Ok(StopBits::One)
+    }
+
+    fn timeout(&self) -> Duration {
+        self.inner.lock().unwrap().timeout
+    }
+
+    fn set_baud_rate(&mut self, baud_rate: u32) -> serialport::Result<()> {
+        self.inner.lock().unwrap().port_baud_rate = baud_rate;
+        Ok(())
+    }
+
+    fn set_data_bits(&mut self, _data_bits: DataBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_flow_control(&mut self, _flow_control: FlowControl) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_parity(&mut self, _parity: Parity) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_stop_bits(&mut self, _stop_bits: StopBits) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn set_timeout(&mut self, timeout: Duration) -> serialport::Result<()> {
+        self.inner.lock().unwrap().timeout = timeout;
+        Ok(())
+    }
+
+    fn write_request_to_send(&mut self, level: bool) -> serialport::Result<()> {
+        self.set_lines(|(_, rts)| *rts = level);
+        Ok(())
+    }
+
+    fn write_data_terminal_ready(&mut self, level: bool) -> serialport::Result<()> {
+        self.set_lines(|(dtr, _)| *dtr = level);
+        Ok(())
+    }
+
+    fn read_clear_to_send(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_data_set_ready(&mut self) -> serialport::Result<bool> {
+        Ok(true)
+    }
+
+    fn read_ring_indicator(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn read_carrier_detect(&mut self) -> serialport::Result<bool> {
+        Ok(false)
+    }
+
+    fn bytes_to_read(&self) -> serialport::Result<u32> {
+        Ok(self.inner.lock().unwrap().to_host.len() as u32)
+    }
+
+    fn bytes_to_write(&self) -> serialport::Result<u32> {
+        Ok(0)
+    }
+
+    fn clear(&self, buffer_to_clear: ClearBuffer) -> serialport::Result<()> {
+        let mut inner = self.inner.lock().unwrap();
+        if matches!(buffer_to_clear, ClearBuffer::Input | ClearBuffer::All) {
+            inner.to_host.clear();
+        }
+        Ok(())
+    }
+
+    fn try_clone(&self) -> serialport::Result<Box<dyn SerialPort>> {
+        Ok(Box::new(self.clone()))
+    }
+
+    fn set_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+
+    fn clear_break(&self) -> serialport::Result<()> {
+        Ok(())
+    }
+}
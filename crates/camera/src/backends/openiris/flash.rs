@@ -0,0 +1,731 @@
+use std::{
+    io::{self, Read, Write},
+    time::{Duration, Instant},
+};
+
+use log::{debug, info, trace, warn};
+use serialport::SerialPort;
+
+use crate::CameraState;
+
+// ESP ROM bootloader protocol, see the serial protocol chapter of the esptool
+// docs. Every packet is SLIP framed:
+// request:  direction (0x00), command, size (u16), checksum (u32), data
+// response: direction (0x01), command, size (u16), value (u32), data
+// The ROM loaders of the ESP32 family end the response data with 4 status
+// bytes, the first two being the status and the error code.
+
+const SLIP_END: u8 = 0xC0;
+const SLIP_ESC: u8 = 0xDB;
+const SLIP_ESC_END: u8 = 0xDC;
+const SLIP_ESC_ESC: u8 = 0xDD;
+
+const FLASH_BEGIN: u8 = 0x02;
+const FLASH_DATA: u8 = 0x03;
+const FLASH_END: u8 = 0x04;
+const SYNC: u8 = 0x08;
+const READ_REG: u8 = 0x0A;
+const SPI_ATTACH: u8 = 0x0D;
+const SPI_FLASH_MD5: u8 = 0x13;
+
+const CHECKSUM_SEED: u8 = 0xEF;
+const STATUS_LEN: usize = 4;
+// largest block the ROM loader accepts
+const BLOCK_SIZE: usize = 0x400;
+const SECTOR_SIZE: u32 = 0x1000;
+const IMAGE_MAGIC: u8 = 0xE9;
+
+// the chip magic value tells the original ESP32 apart from later chips
+const CHIP_DETECT_MAGIC_REG: u32 = 0x4000_1000;
+const ESP32_MAGIC: u32 = 0x00F0_1D83;
+
+// the ROM loader detects the baud rate while syncing, which only works
+// reliably at low rates
+const FLASH_BAUD_RATE: u32 = 115_200;
+const SYNC_ATTEMPTS: usize = 7;
+const SYNC_TIMEOUT: Duration = Duration::from_millis(100);
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(3);
+// erasing and hashing scale with the image size, taken from esptool
+const ERASE_TIMEOUT_PER_MB: Duration = Duration::from_secs(30);
+const MD5_TIMEOUT_PER_MB: Duration = Duration::from_secs(8);
+
+/// Levels of DTR and RTS, held for the given milliseconds
+/// - On the usual auto-reset circuit DTR pulls IO0 and RTS pulls EN low
+type LineStep = (bool, bool, u64);
+
+/// Restarts the device by pulling EN low, keeping IO0 high so it boots the
+/// firmware
+pub(super) const HARD_RESET: &[LineStep] = &[(false, true, 100), (false, false, 0)];
+
+/// Reason flashing firmware failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlashError {
+    /// The image or the target offset is unusable
+    InvalidImage(String),
+    /// The ROM bootloader did not answer, the device is probably not in
+    /// download mode
+    NoBootloader,
+    /// The bootloader did not respond to the command in time
+    Timeout { command: u8 },
+    /// The bootloader rejected the command with the given error code
+    Rejected { command: u8, error: u8 },
+    /// The MD5 of the written flash does not match the image
+    VerifyFailed { expected: String, actual: String },
+    /// The serial connection failed
+    Link(CameraState),
+}
+
+/// Progress of a running flash, reported after every block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlashProgress {
+    pub written: usize,
+    pub total: usize,
+}
+
+/// How the device is put into download mode through the control lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResetMode {
+    /// DTR and RTS wired to IO0 and EN through transistors, as on most
+    /// USB-UART bridges
+    #[default]
+    Classic,
+    /// Native USB Serial/JTAG of the ESP32-S3 and similar chips
+    UsbJtagSerial,
+    /// The device is already in download mode, e.g. by holding the boot
+    /// button
+    None,
+}
+
+/// Writes firmware images to ESP32 based trackers through the ROM bootloader
+/// - Images are written uncompressed and verified by MD5
+/// - OpenIris releases ship merged images, written at offset `0` by default
+#[derive(Debug, Clone, Default)]
+pub struct EspFlasher {
+    offset: u32,
+    reset: ResetMode,
+}
+
+impl EspFlasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overrides the flash offset, e.g. `0x10000` for a bare application
+    /// image
+    pub fn with_offset(mut self, offset: u32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Overrides how the device is reset into download mode
+    pub fn with_reset(mut self, reset: ResetMode) -> Self {
+        self.reset = reset;
+        self
+    }
+
+    /// Resets the device into download mode, writes the image and restarts it
+    /// - The baud rate of the port is restored afterwards
+    pub fn flash_port(
+        &self,
+        port: &mut dyn SerialPort,
+        image: &[u8],
+        progress: impl FnMut(FlashProgress),
+    ) -> Result<(), FlashError> {
+        self.check_image(image)?;
+
+        let baud_rate = port.baud_rate().map_err(serial_error)?;
+        port.set_baud_rate(FLASH_BAUD_RATE).map_err(serial_error)?;
+        let result = self
+            .enter_bootloader(port)
+            .and_then(|()| self.flash(port, image, progress));
+
+        // leave the device running the new firmware, or the old one on failure
+        let reset = set_control_lines(port, HARD_RESET).map_err(serial_error);
+        let restore = port.set_baud_rate(baud_rate).map_err(serial_error);
+        // the outcome of flashing matters most, then that of the cleanup
+        result.and(reset).and(restore)
+    }
+
+    /// Writes the image through a bootloader that is already in download mode
+    pub fn flash<P: Read + Write + ?Sized>(
+        &self,
+        port: &mut P,
+        image: &[u8],
+        mut progress: impl FnMut(FlashProgress),
+    ) -> Result<(), FlashError> {
+        self.check_image(image)?;
+
+        let mut loader = Loader::new(port);
+        loader.sync()?;
+        let magic = loader.command(READ_REG, &CHIP_DETECT_MAGIC_REG.to_le_bytes(), 0)?;
+        debug!("chip magic {magic:#010x}");
+        loader.command(SPI_ATTACH, &[0; 8], 0)?;
+
+        let blocks = image.len().div_ceil(BLOCK_SIZE);
+        info!(
+            "flashing {} bytes at {:#x} in {blocks} blocks",
+            image.len(),
+            self.offset
+        );
+        let mut begin = Vec::new();
+        for word in [image.len(), blocks, BLOCK_SIZE, self.offset as usize] {
+            begin.extend((word as u32).to_le_bytes());
+        }
+        // later chips expect whether to encrypt the written data
+        if magic != ESP32_MAGIC {
+            begin.extend(0u32.to_le_bytes());
+        }
+        loader.command_with_timeout(
+            FLASH_BEGIN,
+            &begin,
+            0,
+            scaled_timeout(ERASE_TIMEOUT_PER_MB, image.len()),
+        )?;
+
+        let mut written = 0;
+        progress(FlashProgress {
+            written,
+            total: image.len(),
+        });
+        for (seq, block) in image.chunks(BLOCK_SIZE).enumerate() {
+            let mut data = Vec::with_capacity(16 + BLOCK_SIZE);
+            for word in [BLOCK_SIZE, seq, 0, 0] {
+                data.extend((word as u32).to_le_bytes());
+            }
+            data.extend_from_slice(block);
+            // the last block is padded with erased flash
+            data.resize(16 + BLOCK_SIZE, 0xFF);
+
+            loader.command(FLASH_DATA, &data, checksum(&data[16..]))?;
+            written += block.len();
+            progress(FlashProgress {
+                written,
+                total: image.len(),
+            });
+        }
+
+        let mut md5 = Vec::new();
+        for word in [self.offset as usize, image.len(), 0, 0] {
+            md5.extend((word as u32).to_le_bytes());
+        }
+        loader.command_with_timeout(
+            SPI_FLASH_MD5,
+            &md5,
+            0,
+            scaled_timeout(MD5_TIMEOUT_PER_MB, image.len()),
+        )?;
+        let expected = format!("{:x}", md5::compute(image));
+        // the ROM loader answers in hex, the flasher stub in raw bytes
+        let actual = match loader.data.as_slice() {
+            hex if hex.len() == 32 => String::from_utf8_lossy(hex).to_lowercase(),
+            raw => raw.iter().map(|byte| format!("{byte:02x}")).collect(),
+        };
+        if actual != expected {
+            warn!("flash verification failed, expected {expected} but got {actual}");
+            return Err(FlashError::VerifyFailed { expected, actual });
+        }
+
+        // stay in the loader, the caller resets the device
+        loader.command(FLASH_END, &1u32.to_le_bytes(), 0)?;
+        info!("flashed and verified {} bytes", image.len());
+
+        Ok(())
+    }
+
+    fn check_image(&self, image: &[u8]) -> Result<(), FlashError> {
+        if image.first() != Some(&IMAGE_MAGIC) {
+            return Err(FlashError::InvalidImage(
+                "missing ESP image header".to_string(),
+            ));
+        }
+        if !self.offset.is_multiple_of(SECTOR_SIZE) {
+            return Err(FlashError::InvalidImage(format!(
+                "offset {:#x} is not sector aligned",
+                self.offset
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn enter_bootloader(&self, port: &mut dyn SerialPort) -> Result<(), FlashError> {
+        let sequence: &[LineStep] = match self.reset {
+            ResetMode::Classic => &[(false, true, 100), (true, false, 50), (false, false, 0)],
+            ResetMode::UsbJtagSerial => &[
+                (false, false, 100),
+                (true, false, 100),
+                (false, true, 100),
+                (false, false, 0),
+            ],
+            ResetMode::None => &[],
+        };
+
+        set_control_lines(port, sequence).map_err(serial_error)
+    }
+}
+
+/// Drives DTR and RTS through the given steps, DTR is set first
+pub(super) fn set_control_lines(
+    port: &mut dyn SerialPort,
+    steps: &[LineStep],
+) -> serialport::Result<()> {
+    for &(dtr, rts, hold) in steps {
+        port.write_data_terminal_ready(dtr)?;
+        port.write_request_to_send(rts)?;
+        std::thread::sleep(Duration::from_millis(hold));
+    }
+
+    Ok(())
+}
+
+fn serial_error(e: serialport::Error) -> FlashError {
+    FlashError::Link(CameraState::Error(format!("serial port error: {e}")))
+}
+
+fn scaled_timeout(per_mb: Duration, len: usize) -> Duration {
+    let scaled = per_mb.mul_f64(len as f64 / 1_000_000.0);
+    scaled.max(COMMAND_TIMEOUT)
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.iter()
+        .fold(CHECKSUM_SEED, |checksum, byte| checksum ^ byte) as u32
+}
+
+fn slip_encode(packet: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(packet.len() + 2);
+    frame.push(SLIP_END);
+    for &byte in packet {
+        match byte {
+            SLIP_END => frame.extend([SLIP_ESC, SLIP_ESC_END]),
+            SLIP_ESC => frame.extend([SLIP_ESC, SLIP_ESC_ESC]),
+            byte => frame.push(byte),
+        }
+    }
+    frame.push(SLIP_END);
+    frame
+}
+
+/// Splits a byte stream into SLIP frames
+/// - Every END byte delimits a frame, anything in between frames such as the
+///   boot log comes out as a frame of its own and fails to parse later on.
+///   This way a stream picked up mid-frame cannot confuse start and end.
+#[derive(Debug, Default)]
+struct SlipDecoder {
+    frame: Vec<u8>,
+    escaped: bool,
+}
+
+impl SlipDecoder {
+    fn push(&mut self, byte: u8) -> Option<Vec<u8>> {
+        match (self.escaped, byte) {
+            (_, SLIP_END) => {
+                self.escaped = false;
+                if !self.frame.is_empty() {
+                    return Some(std::mem::take(&mut self.frame));
+                }
+            }
+            (false, SLIP_ESC) => self.escaped = true,
+            (true, SLIP_ESC_END) => {
+                self.escaped = false;
+                self.frame.push(SLIP_END);
+            }
+            (true, SLIP_ESC_ESC) => {
+                self.escaped = false;
+                self.frame.push(SLIP_ESC);
+            }
+            (_, byte) => {
+                self.escaped = false;
+                self.frame.push(byte);
+            }
+        }
+
+        None
+    }
+}
+
+/// Request/response exchange with the ROM loader
+struct Loader<'a, P: ?Sized> {
+    port: &'a mut P,
+    slip: SlipDecoder,
+    /// Data of the last response without the status bytes
+    data: Vec<u8>,
+}
+
+impl<'a, P: Read + Write + ?Sized> Loader<'a, P> {
+    fn new(port: &'a mut P) -> Self {
+        Self {
+            port,
+            slip: SlipDecoder::default(),
+            data: Vec::new(),
+        }
+    }
+
+    fn sync(&mut self) -> Result<(), FlashError> {
+        let mut data = vec![0x07, 0x07, 0x12, 0x20];
+        data.extend([0x55; 32]);
+
+        for attempt in 1..=SYNC_ATTEMPTS {
+            match self.command_with_timeout(SYNC, &data, 0, SYNC_TIMEOUT) {
+                // the remaining responses to the sync are skipped later on
+                Ok(_) => return Ok(()),
+                Err(FlashError::Timeout { .. }) => trace!("sync attempt {attempt} failed"),
+                Err(e) => return Err(e),
+            }
+        }
+
+        warn!("bootloader did not respond to sync");
+        Err(FlashError::NoBootloader)
+    }
+
+    fn command(&mut self, command: u8, data: &[u8], checksum: u32) -> Result<u32, FlashError> {
+        self.command_with_timeout(command, data, checksum, COMMAND_TIMEOUT)
+    }
+
+    /// Sends the command and returns the value of its response
+    fn command_with_timeout(
+        &mut self,
+        command: u8,
+        data: &[u8],
+        checksum: u32,
+        timeout: Duration,
+    ) -> Result<u32, FlashError> {
+        let mut packet = vec![0x00, command];
+        packet.extend((data.len() as u16).to_le_bytes());
+        packet.extend(checksum.to_le_bytes());
+        packet.extend_from_slice(data);
+        self.port
+            .write_all(&slip_encode(&packet))
+            .and_then(|()| self.port.flush())
+            .map_err(link_error)?;
+
+        let deadline = Instant::now() + timeout;
+        let mut buf = [0u8; 256];
+        while Instant::now() < deadline {
+            let read = match self.port.read(&mut buf) {
+                Ok(0) => return Err(FlashError::Link(CameraState::ReadFailed)),
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::TimedOut => continue,
+                Err(e) => return Err(link_error(e)),
+            };
+
+            for &byte in &buf[..read] {
+                let Some(frame) = self.slip.push(byte) else {
+                    continue;
+                };
+                if let Some(value) = self.parse_response(&frame, command)? {
+                    return Ok(value);
+                }
+            }
+        }
+
+        Err(FlashError::Timeout { command })
+    }
+
+    /// Returns `None` if the frame is no response to the command
+    fn parse_response(&mut self, frame: &[u8], command: u8) -> Result<Option<u32>, FlashError> {
+        let &[
+            0x01,
+            response,
+            size_lo,
+            size_hi,
+            v0,
+            v1,
+            v2,
+            v3,
+            ref data @ ..,
+        ] = frame
+        else {
+            trace!("ignoring malformed frame of {} bytes", frame.len());
+            return Ok(None);
+        };
+        if response != command {
+            trace!("ignoring response to command {response:#04x}");
+            return Ok(None);
+        }
+
+        let size = u16::from_le_bytes([size_lo, size_hi]) as usize;
+        if size < STATUS_LEN || data.len() != size {
+            trace!("ignoring response with invalid size {size}");
+            return Ok(None);
+        }
+
+        let (data, status) = data.split_at(size - STATUS_LEN);
+        if status[0] != 0 {
+            warn!(
+                "command {command:#04x} failed with error {:#04x}",
+                status[1]
+            );
+            return Err(FlashError::Rejected {
+                command,
+                error: status[1],
+            });
+        }
+
+        self.data = data.to_vec();
+        Ok(Some(u32::from_le_bytes([v0, v1, v2, v3])))
+    }
+}
+
+fn link_error(e: io::Error) -> FlashError {
+    FlashError::Link(super::io_error_state(e))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::{super::fake::FakeDevice, *};
+
+    const FLASH_SIZE: usize = 4 * 1024 * 1024;
+
+    /// Stand-in for the ROM loader of an ESP32 in download mode
+    struct Bootloader {
+        magic: u32,
+        flash: Vec<u8>,
+        to_host: VecDeque<u8>,
+        slip: SlipDecoder,
+        synced: bool,
+        /// Offset and next sequence number of the running write
+        writing: Option<(usize, u32)>,
+        /// Flips a bit of every written block, failing verification
+        faulty: bool,
+    }
+
+    impl Bootloader {
+        fn new(magic: u32) -> Self {
+            let mut to_host = VecDeque::new();
+            to_host.extend(b"ets Jun  8 2016 00:22:57\r\n\r\nrst:0x1 (POWERON_RESET)\r\n");
+            to_host.extend(b"waiting for download\r\n");
+
+            Self {
+                magic,
+                flash: vec![0xFF; FLASH_SIZE],
+                to_host,
+                slip: SlipDecoder::default(),
+                synced: false,
+                writing: None,
+                faulty: false,
+            }
+        }
+
+        fn respond(&mut self, command: u8, value: u32, data: &[u8], error: Option<u8>) {
+            let mut packet = vec![0x01, command];
+            packet.extend(((data.len() + STATUS_LEN) as u16).to_le_bytes());
+            packet.extend(value.to_le_bytes());
+            packet.extend_from_slice(data);
+            packet.extend([error.is_some() as u8, error.unwrap_or(0), 0, 0]);
+            self.to_host.extend(slip_encode(&packet));
+        }
+
+        fn handle(&mut self, packet: &[u8]) {
+            let &[0x00, command, _, _, c0, c1, c2, c3, ref data @ ..] = packet else {
+                return;
+            };
+            let word = |index: usize| {
+                let bytes = data[index * 4..index * 4 + 4].try_into().unwrap();
+                u32::from_le_bytes(bytes) as usize
+            };
+
+            if command == SYNC {
+                self.synced = true;
+                for _ in 0..8 {
+                    self.respond(SYNC, 0, &[], None);
+                }
+                return;
+            }
+            if !self.synced {
+                return;
+            }
+
+            match command {
+                READ_REG => self.respond(command, self.magic, &[], None),
+                SPI_ATTACH => self.respond(command, 0, &[], None),
+                FLASH_BEGIN => {
+                    let params = if self.magic == ESP32_MAGIC { 16 } else { 20 };
+                    if data.len() != params {
+                        return self.respond(command, 0, &[], Some(0x05));
+                    }
+                    let (len, offset) = (word(0), word(3));
+                    self.flash[offset..offset + len].fill(0xFF);
+                    self.writing = Some((offset, 0));
+                    self.respond(command, 0, &[], None);
+                }
+                FLASH_DATA => {
+                    let Some((offset, seq)) = self.writing else {
+                        return self.respond(command, 0, &[], Some(0x06));
+                    };
+                    let block = &data[16..];
+                    if u32::from_le_bytes([c0, c1, c2, c3]) != checksum(block) {
+                        return self.respond(command, 0, &[], Some(0x07));
+                    }
+                    if word(1) as u32 != seq {
+                        return self.respond(command, 0, &[], Some(0x05));
+                    }
+
+                    let start = offset + seq as usize * BLOCK_SIZE;
+                    self.flash[start..start + block.len()].copy_from_slice(block);
+                    if self.faulty {
+                        self.flash[start] ^= 0x01;
+                    }
+                    self.writing = Some((offset, seq + 1));
+                    self.respond(command, 0, &[], None);
+                }
+                SPI_FLASH_MD5 => {
+                    let (offset, len) = (word(0), word(1));
+                    let md5 = format!("{:X}", md5::compute(&self.flash[offset..offset + len]));
+                    self.respond(command, 0, md5.as_bytes(), None);
+                }
+                FLASH_END => {
+                    self.writing = None;
+                    self.respond(command, 0, &[], None);
+                }
+                _ => self.respond(command, 0, &[], Some(0x05)),
+            }
+        }
+    }
+
+    impl Read for Bootloader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.to_host.is_empty() {
+                std::thread::sleep(Duration::from_millis(1));
+                return Err(io::ErrorKind::TimedOut.into());
+            }
+
+            let len = buf.len().min(self.to_host.len());
+            for (byte, sent) in buf.iter_mut().zip(self.to_host.drain(..len)) {
+                *byte = sent;
+            }
+            Ok(len)
+        }
+    }
+
+    impl Write for Bootloader {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            for &byte in buf {
+                if let Some(packet) = self.slip.push(byte) {
+                    self.handle(&packet);
+                }
+            }
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn image(len: usize) -> Vec<u8> {
+        // includes bytes that need escaping
+        let mut image: Vec<u8> = (0..len).map(|i| (i * 7 % 251) as u8).collect();
+        image[0] = IMAGE_MAGIC;
+        image[1] = SLIP_END;
+        image[2] = SLIP_ESC;
+        image
+    }
+
+    #[test]
+    fn test_slip() {
+        let packet = [0x01, SLIP_END, 0x02, SLIP_ESC, 0x03];
+        let frame = slip_encode(&packet);
+        assert_eq!(
+            frame,
+            [0xC0, 0x01, 0xDB, 0xDC, 0x02, 0xDB, 0xDD, 0x03, 0xC0]
+        );
+
+        let mut slip = SlipDecoder::default();
+        let mut stream = b"boot log".to_vec();
+        stream.extend(&frame);
+        let frames: Vec<_> = stream.iter().filter_map(|&byte| slip.push(byte)).collect();
+        assert_eq!(frames, [b"boot log".to_vec(), packet.to_vec()]);
+    }
+
+    #[test]
+    fn test_flash() {
+        for magic in [ESP32_MAGIC, 0x09] {
+            let image = image(3 * BLOCK_SIZE + 100);
+            let mut bootloader = Bootloader::new(magic);
+            let mut reports = Vec::new();
+
+            EspFlasher::new()
+                .with_offset(0x10000)
+                .flash(&mut bootloader, &image, |progress| reports.push(progress))
+                .expect("flashing should succeed");
+
+            assert_eq!(&bootloader.flash[0x10000..0x10000 + image.len()], image);
+            assert_eq!(bootloader.flash[0x10000 + image.len()], 0xFF);
+            assert_eq!(reports.len(), 5);
+            assert!(reports.windows(2).all(|w| w[0].written < w[1].written));
+            assert_eq!(reports.last().unwrap().written, image.len());
+        }
+    }
+
+    #[test]
+    fn test_flash_failures() {
+        let mut bootloader = Bootloader::new(ESP32_MAGIC);
+        bootloader.faulty = true;
+        let result = EspFlasher::new().flash(&mut bootloader, &image(2000), |_| {});
+        assert!(matches!(result, Err(FlashError::VerifyFailed { .. })));
+
+        // running firmware instead of the bootloader
+        let mut firmware = Firmware(io::Cursor::new(b"I (312) cpu_start: Starting".to_vec()));
+        let result = EspFlasher::new().flash(&mut firmware, &image(2000), |_| {});
+        assert_eq!(result, Err(FlashError::NoBootloader));
+
+        let result = EspFlasher::new().flash(&mut bootloader, b"not an image", |_| {});
+        assert!(matches!(result, Err(FlashError::InvalidImage(_))));
+    }
+
+    #[test]
+    fn test_reset_lines() {
+        // the fake device runs firmware, flashing fails after the resets
+        let device = FakeDevice::tracker();
+        let mut port = device.clone();
+        let result = EspFlasher::new().flash_port(&mut port, &image(100), |_| {});
+        assert_eq!(result, Err(FlashError::NoBootloader));
+        assert_eq!(port.baud_rate().unwrap(), 3_000_000);
+        assert_eq!(
+            device.control_lines(),
+            [
+                // download mode, IO0 is low once EN is released
+                (false, true),
+                (true, true),
+                (true, false),
+                (false, false),
+                // hard reset
+                (false, true),
+                (false, false),
+            ]
+        );
+
+        let device = FakeDevice::tracker();
+        let flasher = EspFlasher::new().with_reset(ResetMode::None);
+        let _ = flasher.flash_port(&mut device.clone(), &image(100), |_| {});
+        assert_eq!(device.control_lines(), [(false, true), (false, false)]);
+    }
+
+    /// Logs a line and ignores everything sent to it
+    struct Firmware(io::Cursor<Vec<u8>>);
+
+    impl Read for Firmware {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match self.0.read(buf)? {
+                0 => {
+                    std::thread::sleep(Duration::from_millis(1));
+                    Err(io::ErrorKind::TimedOut.into())
+                }
+                read => Ok(read),
+            }
+        }
+    }
+
+    impl Write for Firmware {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+
+use serde_json::Value;
+
+use super::StreamingMode;
+
+/// Version of the OpenIris firmware, e.g. `v5.2.1`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FirmwareVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+}
+
+impl FirmwareVersion {
+    /// Oldest firmware known to work reliably, earlier releases lack the
+    /// command protocol
+    pub const MINIMUM: FirmwareVersion = FirmwareVersion::new(5, 0, 0);
+
+    pub const fn new(major: u16, minor: u16, patch: u16) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Parses versions like `v5.2.1`, `5.2` or `5.2.1-rc1`
+    /// - Pre-release suffixes are ignored
+    pub fn parse(version: &str) -> Option<FirmwareVersion> {
+        let version = version.trim().trim_start_matches(['v', 'V']);
+        let version = version.split(['-', '+']).next()?;
+
+        let mut parts = version.split('.').map(str::parse::<u16>);
+        let major = parts.next()?.ok()?;
+        let minor = parts.next().unwrap_or(Ok(0)).ok()?;
+        let patch = parts.next().unwrap_or(Ok(0)).ok()?;
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(FirmwareVersion::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for FirmwareVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// What to do when a device runs firmware below the minimum version or does
+/// not report its version at all
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FirmwarePolicy {
+    /// Log a warning and stream anyway
+    #[default]
+    Warn,
+    /// Fail to connect
+    Refuse,
+}
+
+/// Device information reported by the firmware after connecting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceInfo {
+    pub firmware_version: FirmwareVersion,
+    /// Board the firmware was built for, e.g. `xiao_esp32s3`
+    pub board: String,
+    /// Supported frame sizes as width and height
+    pub resolutions: Vec<(u32, u32)>,
+    /// `None` if the firmware reported an unknown mode
+    pub streaming_mode: Option<StreamingMode>,
+}
+
+impl DeviceInfo {
+    /// Parses the data of a `get_device_info` response
+    /// - Resolutions are given as `"240x240"`, malformed entries are skipped
+    pub(crate) fn parse(data: &Value) -> Option<DeviceInfo> {
+        let resolutions = data["resolutions"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|resolution| {
+                let (width, height) = resolution.as_str()?.split_once('x')?;
+                Some((width.parse().ok()?, height.parse().ok()?))
+            })
+            .collect();
+
+        Some(DeviceInfo {
+            firmware_version: FirmwareVersion::parse(data["version"].as_str()?)?,
+            board: data["board"].as_str().unwrap_or_default().to_string(),
+            resolutions,
+            streaming_mode: data["mode"].as_str().and_then(StreamingMode::parse),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            FirmwareVersion::parse("v5.2.1"),
+            Some(FirmwareVersion::new(5, 2, 1))
+        );
+        assert_eq!(
+            FirmwareVersion::parse("4.1-rc2"),
+            Some(FirmwareVersion::new(4, 1, 0))
+        );
+        assert_eq!(FirmwareVersion::parse("5.x"), None);
+        assert_eq!(FirmwareVersion::parse("1.2.3.4"), None);
+        assert!(FirmwareVersion::new(4, 9, 9) < FirmwareVersion::MINIMUM);
+    }
+
+    #[test]
+    fn test_parse_info() {
+        let info = DeviceInfo::parse(&json!({
+            "version": "v5.1.0",
+            "board": "xiao_esp32s3",
+            "resolutions": ["240x240", "320x240", "broken"],
+            "mode": "serial",
+        }))
+        .expect("info should parse");
+        assert_eq!(info.firmware_version, FirmwareVersion::new(5, 1, 0));
+        assert_eq!(info.board, "xiao_esp32s3");
+        assert_eq!(info.resolutions, [(240, 240), (320, 240)]);
+        assert_eq!(info.streaming_mode, Some(StreamingMode::Serial));
+
+        assert_eq!(DeviceInfo::parse(&json!({ "board": "esp32cam" })), None);
+    }
+}
@@ -0,0 +1,475 @@
+use std::{
+    collections::VecDeque,
+    fmt, io,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use log::{debug, info, trace, warn};
+use serialport::SerialPort;
+
+use crate::{CameraHandler, CameraState, Frame};
+
+mod command;
+mod config;
+mod decoder;
+mod discovery;
+#[cfg(test)]
+mod fake;
+mod flash;
+mod info;
+mod provisioning;
+mod watcher;
+
+pub use command::{OpenIrisCommand, OpenIrisCommander, StreamingMode};
+pub use config::{BaudRate, PortOpener, SerialConfig};
+pub use decoder::{OpenIrisDecoder, OpenIrisMessage};
+pub use discovery::{OpenIrisDevice, OpenIrisDiscovery, UsbBridge};
+pub use flash::{EspFlasher, FlashError, FlashProgress, ResetMode};
+pub use info::{DeviceInfo, FirmwarePolicy, FirmwareVersion};
+pub use provisioning::{ProvisioningError, WifiProvisioning, WifiStatus};
+pub use watcher::{DeviceEvent, SerialWatcher};
+
+const BAUD_RATE: u32 = if cfg!(target_os = "macos") {
+    // as per EyeTrackVR python: higher baud rate not working on macOS
+    115_200
+} else {
+    3_000_000
+};
+// upper bound for a single read, roughly a handful of frames
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+// frames read while a command waits for its response, only the newest is
+// handed out anyway
+const MAX_PENDING_PACKETS: usize = 2;
+const MAX_PENDING_LINES: usize = 64;
+
+type SerialPortVariant = Box<dyn SerialPort>;
+
+/// The serial port shared between frame capture and [`OpenIrisCommander`]
+#[derive(Debug, Default)]
+struct Link {
+    io: Mutex<LinkIo>,
+    /// Held while a command waits for its response
+    command: Mutex<()>,
+}
+
+#[derive(Debug, Default)]
+struct LinkIo {
+    port: Option<SerialPortVariant>,
+    decoder: OpenIrisDecoder,
+    packets: VecDeque<Vec<u8>>,
+    /// Lines that look like command responses
+    lines: VecDeque<String>,
+    info: Option<DeviceInfo>,
+}
+
+impl Link {
+    fn io(&self) -> MutexGuard<'_, LinkIo> {
+        self.io.lock().expect("serial link lock poisoned")
+    }
+}
+
+impl LinkIo {
+    fn attach(&mut self, port: SerialPortVariant) {
+        self.port = Some(port);
+        self.decoder.clear();
+        self.packets.clear();
+        self.lines.clear();
+        self.info = None;
+    }
+
+    /// Reads the available bytes once and sorts out the decoded messages
+    fn pump(&mut self) -> Result<(), CameraState> {
+        let port = self.port.as_mut().ok_or(CameraState::Disconnected)?;
+
+        // read whatever is available, blocking for at least one byte so the
+        // port timeout still applies
+        let available = match port.bytes_to_read() {
+            Ok(available) => (available as usize).clamp(1, READ_CHUNK_SIZE),
+            Err(e) => {
+                trace!("failed to query available bytes in serial port: {e:?}");
+                1
+            }
+        };
+
+        let mut buf = vec![0u8; available];
+        let read = match port.read(&mut buf) {
+            Ok(0) => return Err(CameraState::ReadFailed),
+            Ok(read) => read,
+            Err(e) => {
+                trace!("failed to read bytes from serial port buffer: {e:?}");
+                return Err(io_error_state(e));
+            }
+        };
+        trace!("read {read} bytes from serial port");
+        self.decoder.feed(&buf[..read]);
+
+        while let Some(message) = self.decoder.next_message() {
+            match message {
+                OpenIrisMessage::Packet(packet) => {
+                    if self.packets.len() == MAX_PENDING_PACKETS {
+                        self.packets.pop_front();
+                    }
+                    self.packets.push_back(packet);
+                }
+                OpenIrisMessage::Line(line) if line.starts_with('{') => {
+                    if self.lines.len() == MAX_PENDING_LINES {
+                        self.lines.pop_front();
+                    }
+                    self.lines.push_back(line);
+                }
+                OpenIrisMessage::Line(line) => debug!("firmware: {line}"),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), CameraState> {
+        let port = self.port.as_mut().ok_or(CameraState::Disconnected)?;
+        port.write_all(line.as_bytes())
+            .and_then(|()| port.write_all(b"\n"))
+            .and_then(|()| port.flush())
+            .map_err(io_error_state)
+    }
+}
+
+pub struct OpenIrisCamera {
+    link: Arc<Link>,
+    config: SerialConfig,
+    opener: Option<PortOpener>,
+    min_firmware: FirmwareVersion,
+    firmware_policy: FirmwarePolicy,
+}
+
+impl OpenIrisCamera {
+    /// Overrides the platform default baud rate
+    pub fn with_baud_rate(mut self, baud_rate: u32) -> Self {
+        self.config.baud_rate = BaudRate::Fixed(baud_rate);
+        self
+    }
+
+    /// Overrides all serial port settings, see [`SerialConfig::from_source`]
+    pub fn with_serial_config(mut self, config: SerialConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Opens ports through the given opener instead of the system serial
+    /// ports
+    pub fn with_port_opener(mut self, opener: PortOpener) -> Self {
+        self.opener = Some(opener);
+        self
+    }
+
+    /// Overrides the minimum firmware version, [`FirmwareVersion::MINIMUM`] by
+    /// default
+    pub fn with_min_firmware(mut self, version: FirmwareVersion) -> Self {
+        self.min_firmware = version;
+        self
+    }
+
+    /// Determines how devices with outdated firmware are handled, they are
+    /// only warned about by default
+    pub fn with_firmware_policy(mut self, policy: FirmwarePolicy) -> Self {
+        self.firmware_policy = policy;
+        self
+    }
+
+    /// Returns the device info queried while connecting, see
+    /// [`OpenIrisCommander::device_info`]
+    pub fn device_info(&self) -> Option<DeviceInfo> {
+        self.link.io().info.clone()
+    }
+
+    /// Returns a handle for sending commands to the device
+    /// - Stays valid after the camera was handed to [`crate::Camera`] and
+    ///   across reconnects
+    pub fn commander(&self) -> OpenIrisCommander {
+        OpenIrisCommander::new(self.link.clone())
+    }
+
+    /// Attaches the opened port and checks the firmware of the device
+    fn start(&mut self, port: SerialPortVariant) -> Result<(), CameraState> {
+        self.link.io().attach(port);
+
+        let info = match self.commander().probe_device_info() {
+            Ok(info) => info,
+            Err(e) => {
+                self.disconnect();
+                return Err(e);
+            }
+        };
+
+        let version = info.as_ref().map(|info| info.firmware_version);
+        match &info {
+            Some(info) => info!(
+                "firmware {} on {} streaming via {:?}",
+                info.firmware_version, info.board, info.streaming_mode
+            ),
+            None => warn!("device did not report its firmware version"),
+        }
+        if version.is_none_or(|version| version < self.min_firmware) {
+            let outdated = match version {
+                Some(version) => format!("firmware {version} is outdated"),
+                None => "firmware is outdated".to_string(),
+            };
+
+            match self.firmware_policy {
+                FirmwarePolicy::Warn => {
+                    warn!("{outdated}, at least {} is recommended", self.min_firmware);
+                }
+                FirmwarePolicy::Refuse => {
+                    self.disconnect();
+                    return Err(CameraState::Error(format!(
+                        "{outdated}, at least {} is required",
+                        self.min_firmware
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl CameraHandler for OpenIrisCamera {
+    fn init() -> Self
+    where
+        Self: Sized,
+    {
+        Self {
+            link: Arc::default(),
+            config: SerialConfig::default(),
+            opener: None,
+            min_firmware: FirmwareVersion::MINIMUM,
+            firmware_policy: FirmwarePolicy::default(),
+        }
+    }
+
+    fn get_frame(&mut self) -> Result<Frame, CameraState> {
+        loop {
+            // released between reads, letting commands through
+            let mut io = self.link.io();
+
+            // only hand out the newest frame to keep in sync with reality
+            if let Some(packet) = io.packets.pop_back() {
+                if !io.packets.is_empty() {
+                    trace!("skipping {} stale frames", io.packets.len());
+                    io.packets.clear();
+                }
+                return Ok(Frame::jpeg(packet));
+            }
+
+            io.pump()?;
+        }
+    }
+
+    fn connect(&mut self, source: String) -> Result<(), CameraState> {
+        if self.link.io().port.is_none() {
+            let source = source.as_str();
+            info!("connecting to {source}");
+
+            let port = self.config.open(source, self.opener.as_ref())?;
+            info!("connected to serial port {source}");
+            self.start(port)
+        } else {
+            Err(CameraState::Connected)
+        }
+    }
+
+    fn disconnect(&mut self) {
+        // port is closed once object is dropped
+        let mut io = self.link.io();
+        io.port = None;
+        io.decoder.clear();
+    }
+}
+
+impl fmt::Debug for OpenIrisCamera {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OpenIrisCamera")
+            .field("link", &self.link)
+            .field("config", &self.config)
+            .field("opener", &self.opener.as_ref().map(|_| "custom"))
+            .field("min_firmware", &self.min_firmware)
+            .field("firmware_policy", &self.firmware_policy)
+            .finish()
+    }
+}
+
+/// Maps serial port errors to the matching camera state
+fn io_error_state(e: io::Error) -> CameraState {
+    match e.kind() {
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => CameraState::Timeout,
+        io::ErrorKind::UnexpectedEof => CameraState::ReadFailed,
+        io::ErrorKind::BrokenPipe
+        | io::ErrorKind::NotFound
+        | io::ErrorKind::NotConnected
+        | io::ErrorKind::PermissionDenied => CameraState::Disconnected,
+        _ => CameraState::Error(format!("serial port error: {e}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use serde_json::json;
+
+    use super::{
+        fake::{self, FakeDevice},
+        *,
+    };
+    use crate::{Camera, ReconnectPolicy};
+
+    fn device(version: &'static str) -> FakeDevice {
+        FakeDevice::new(move |command| {
+            let name = command["command"].as_str()?;
+            Some(match name {
+                "get_device_info" => json!({
+                    "command": name,
+                    "status": "ok",
+                    "data": {
+                        "version": version,
+                        "board": "xiao_esp32s3",
+                        "resolutions": ["240x240"],
+                        "mode": "serial",
+                    },
+                }),
+                _ => json!({ "command": name, "status": "error", "error": "Unknown command" }),
+            })
+        })
+    }
+
+    #[test]
+    fn test_firmware_check() {
+        let mut camera = OpenIrisCamera::init().with_firmware_policy(FirmwarePolicy::Refuse);
+        camera
+            .start(Box::new(device("v5.1.0")))
+            .expect("current firmware should be accepted");
+        let info = camera.device_info().expect("info should be queried");
+        assert_eq!(info.firmware_version, FirmwareVersion::new(5, 1, 0));
+        assert_eq!(camera.commander().device_info(), Some(info));
+
+        assert!(matches!(
+            camera.start(Box::new(device("v4.2.0"))),
+            Err(CameraState::Error(_))
+        ));
+        assert!(camera.link.io().port.is_none());
+
+        // firmware without the query is only warned about by default
+        let mut camera = OpenIrisCamera::init();
+        let legacy = FakeDevice::new(|command| {
+            let name = command["command"].as_str()?;
+            Some(json!({ "command": name, "status": "error", "error": "Unknown command" }))
+        });
+        camera
+            .start(Box::new(legacy))
+            .expect("outdated firmware should only be warned about");
+        assert_eq!(camera.device_info(), None);
+
+        // ignoring the query only costs the short probe timeout
+        let started = Instant::now();
+        camera
+            .start(Box::new(FakeDevice::new(|_| None)))
+            .expect("silent firmware should only be warned about");
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // other errors are not mistaken for outdated firmware
+        let broken = FakeDevice::new(|command| {
+            let name = command["command"].as_str()?;
+            Some(json!({ "command": name, "status": "error", "error": "camera init failed" }))
+        });
+        assert!(matches!(
+            camera.start(Box::new(broken)),
+            Err(CameraState::Error(_))
+        ));
+        assert!(camera.link.io().port.is_none());
+    }
+
+    #[test]
+    fn test_scripted_stream() {
+        let device = FakeDevice::tracker();
+        // boot log, a frame cut short by a reset and line noise between frames
+        let mut script = b"ets Jul 29 2019 12:21:46\r\nrst:0x1 (POWERON_RESET)\r\n".to_vec();
+        script.extend(&fake::packet(b"\xFF\xD8lost")[..6]);
+        script.extend(fake::packet(b"\xFF\xD8first\xFF\xD9"));
+        script.extend(b"\xFE\x13\x37\r\n");
+        script.extend(fake::packet(b"\xFF\xD8second\xFF\xD9"));
+        device.play(&script);
+
+        let mut camera = OpenIrisCamera::init().with_port_opener(device.opener());
+        camera
+            .connect("/dev/ttyVIRT0".into())
+            .expect("virtual port should connect");
+        assert_eq!(
+            camera.connect("/dev/ttyVIRT0".into()),
+            Err(CameraState::Connected)
+        );
+        assert!(camera.device_info().is_some());
+
+        // frames that arrived together are skipped but for the newest
+        let frame = camera.get_frame().expect("scripted frame should decode");
+        assert_eq!(frame.data, b"\xFF\xD8second\xFF\xD9");
+        assert_eq!(camera.get_frame().err(), Some(CameraState::Timeout));
+
+        device.unplug();
+        assert_eq!(camera.get_frame().err(), Some(CameraState::Disconnected));
+        camera.disconnect();
+        assert!(camera.connect("/dev/ttyVIRT0".into()).is_err());
+    }
+
+    #[test]
+    fn test_camera_end_to_end() {
+        const FRAMES: usize = 10;
+
+        let device = FakeDevice::tracker().with_timing();
+        device.set_streaming(true);
+        let handler = OpenIrisCamera::init()
+            .with_baud_rate(115_200)
+            .with_port_opener(device.opener());
+
+        let mut camera = Camera::from_camera_handler(Box::new(handler), 1000);
+        camera.set_reconnect_policy(Some(ReconnectPolicy {
+            error_threshold: 3,
+            initial_backoff: Duration::from_millis(20),
+            ..Default::default()
+        }));
+        camera
+            .connect("/dev/ttyVIRT0".into())
+            .expect("virtual port should connect");
+
+        let next_frame = || {
+            camera
+                .get_frame_timeout(Duration::from_secs(2))
+                .expect("frames should keep flowing")
+        };
+        let first = next_frame();
+        let start = Instant::now();
+        for _ in 0..FRAMES {
+            let frame = next_frame();
+            assert_eq!((frame.width, frame.height), (8, 8));
+            assert!(frame.sequence > first.sequence);
+        }
+        // every frame takes about 36 ms over the wire at 115200 baud
+        let min_duration = Duration::from_secs_f64(FRAMES as f64 * 414.0 * 10.0 / 115_200.0);
+        assert!(start.elapsed() >= min_duration.mul_f64(0.9));
+
+        // the handler thread reconnects once the tracker is back
+        device.unplug();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while camera.status() == CameraState::Connected {
+            assert!(Instant::now() < deadline, "unplugging should be noticed");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        device.replug();
+        while camera.try_get_frame().is_ok_and(|frame| frame.is_some()) {}
+        next_frame();
+        assert_eq!(camera.status(), CameraState::Connected);
+
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+    }
+}
@@ -0,0 +1,306 @@
+use std::{
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+use log::{debug, info, warn};
+use serde_json::Value;
+
+use super::{OpenIrisCommand, OpenIrisCommander, StreamingMode, command::is_unknown_command};
+use crate::{CameraSource, CameraState};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+// port of the mjpeg stream served by the firmware
+const STREAM_PORT: u16 = 81;
+// the firmware keeps several networks, provisioning always replaces this one
+const NETWORK_NAME: &str = "main";
+
+/// WiFi connection as reported by the firmware
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WifiStatus {
+    Disconnected,
+    Connecting,
+    Connected {
+        ip: IpAddr,
+    },
+    /// The last attempt failed, e.g. with `auth_failed` or `no_ap_found`
+    Failed {
+        reason: String,
+    },
+}
+
+impl WifiStatus {
+    fn parse(data: &Value) -> Option<WifiStatus> {
+        Some(match data["status"].as_str()? {
+            "disconnected" => WifiStatus::Disconnected,
+            "connecting" => WifiStatus::Connecting,
+            "connected" => WifiStatus::Connected {
+                ip: data["ip_address"].as_str()?.parse().ok()?,
+            },
+            "failed" => WifiStatus::Failed {
+                reason: data["reason"].as_str().unwrap_or_default().to_string(),
+            },
+            _ => return None,
+        })
+    }
+}
+
+/// Reason provisioning a tracker failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProvisioningError {
+    /// The network rejected the password
+    WrongPassword,
+    /// No network with the given SSID is in range
+    NetworkNotFound,
+    /// The tracker did not connect to the network in time
+    Timeout,
+    /// The firmware does not understand the provisioning commands, usually a
+    /// release predating the JSON command protocol
+    UnsupportedFirmware,
+    /// The tracker reported an error
+    Failed(String),
+    /// The serial connection failed
+    Link(CameraState),
+}
+
+/// Sets up a tracker for wireless streaming over its serial port
+/// - Stores the credentials and hostname, waits for the tracker to join the
+///   network and switches it to WiFi streaming
+/// - The tracker restarts at the end, serial frames stop and the port may
+///   disappear for a moment
+#[derive(Debug, Clone)]
+pub struct WifiProvisioning {
+    ssid: String,
+    password: String,
+    hostname: Option<String>,
+    channel: u8,
+    timeout: Duration,
+}
+
+impl WifiProvisioning {
+    pub fn new(ssid: impl Into<String>, password: impl Into<String>) -> Self {
+        Self {
+            ssid: ssid.into(),
+            password: password.into(),
+            hostname: None,
+            channel: 0,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Sets the device name, reachable as `<hostname>.local` afterwards
+    pub fn with_hostname(mut self, hostname: impl Into<String>) -> Self {
+        self.hostname = Some(hostname.into());
+        self
+    }
+
+    /// Pins the WiFi channel, `0` scans all channels
+    pub fn with_channel(mut self, channel: u8) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    /// Overrides how long to wait for the tracker to join the network
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Provisions the tracker behind the commander
+    /// - Returns the source of the wireless stream, ready for
+    ///   [`crate::Camera::open`]
+    pub fn provision(
+        &self,
+        commander: &OpenIrisCommander,
+    ) -> Result<CameraSource, ProvisioningError> {
+        match commander.ping() {
+            Ok(()) => {}
+            // old firmware streams frames but never answers
+            Err(CameraState::Timeout) => return Err(ProvisioningError::UnsupportedFirmware),
+            Err(e) => return Err(ProvisioningError::Link(e)),
+        }
+
+        request(
+            commander,
+            &OpenIrisCommand::SetWifi {
+                name: NETWORK_NAME.to_string(),
+                ssid: self.ssid.clone(),
+                password: self.password.clone(),
+                channel: self.channel,
+            },
+        )?;
+        if let Some(hostname) = &self.hostname {
+            request(
+                commander,
+                &OpenIrisCommand::SetMdns {
+                    hostname: hostname.clone(),
+                },
+            )?;
+        }
+
+        info!("connecting tracker to {}", self.ssid);
+        request(commander, &OpenIrisCommand::ConnectWifi)?;
+        let ip = self.wait_for_ip(commander)?;
+        info!("tracker connected to {} as {ip}", self.ssid);
+
+        request(commander, &OpenIrisCommand::SwitchMode(StreamingMode::Wifi))?;
+        // the connection drops before or right after the response
+        if let Ok(Err(e)) = commander.request(&OpenIrisCommand::RestartDevice) {
+            warn!("failed to restart tracker: {e}");
+        }
+
+        let host = match ip {
+            IpAddr::V4(ip) => ip.to_string(),
+            IpAddr::V6(ip) => format!("[{ip}]"),
+        };
+        CameraSource::parse(&format!("http://{host}:{STREAM_PORT}/"))
+            .map_err(ProvisioningError::Link)
+    }
+
+    fn wait_for_ip(&self, commander: &OpenIrisCommander) -> Result<IpAddr, ProvisioningError> {
+        let deadline = Instant::now() + self.timeout;
+
+        loop {
+            let data = request(commander, &OpenIrisCommand::GetWifiStatus)?;
+            match WifiStatus::parse(&data) {
+                Some(WifiStatus::Connected { ip }) => return Ok(ip),
+                Some(WifiStatus::Failed { reason }) => return Err(failure(reason)),
+                Some(status) => debug!("waiting for tracker to connect: {status:?}"),
+                None => warn!("unexpected wifi status: {data}"),
+            }
+
+            if Instant::now() + POLL_INTERVAL >= deadline {
+                warn!("tracker did not connect to {} in time", self.ssid);
+                return Err(ProvisioningError::Timeout);
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+fn request(
+    commander: &OpenIrisCommander,
+    command: &OpenIrisCommand,
+) -> Result<Value, ProvisioningError> {
+    match commander.request(command) {
+        Ok(Ok(data)) => Ok(data),
+        Ok(Err(e)) if is_unknown_command(&e) => {
+            warn!("firmware does not support {}", command.name());
+            Err(ProvisioningError::UnsupportedFirmware)
+        }
+        Ok(Err(e)) => {
+            warn!("command {} failed: {e}", command.name());
+            Err(ProvisioningError::Failed(e))
+        }
+        Err(e) => Err(ProvisioningError::Link(e)),
+    }
+}
+
+fn failure(reason: String) -> ProvisioningError {
+    match reason.as_str() {
+        "auth_failed" | "wrong_password" | "handshake_timeout" => ProvisioningError::WrongPassword,
+        "no_ap_found" => ProvisioningError::NetworkNotFound,
+        _ => ProvisioningError::Failed(reason),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{super::fake::FakeDevice, *};
+    use crate::{CameraHandler, OpenIrisCamera};
+
+    /// Answers like a tracker joining a network with the given password
+    fn tracker(password: &'static str) -> FakeDevice {
+        let mut stored = None;
+        let mut polls = 0;
+
+        FakeDevice::new(move |command| {
+            let name = command["command"].as_str()?;
+            let data = match name {
+                "set_wifi" => {
+                    stored = command["data"]["password"].as_str().map(str::to_string);
+                    Value::Null
+                }
+                "get_wifi_status" => {
+                    polls += 1;
+                    if polls < 3 {
+                        json!({ "status": "connecting" })
+                    } else if stored.as_deref() == Some(password) {
+                        json!({ "status": "connected", "ip_address": "192.168.1.42" })
+                    } else {
+                        json!({ "status": "failed", "reason": "auth_failed" })
+                    }
+                }
+                // the device restarts without answering
+                "restart_device" => return None,
+                _ => Value::Null,
+            };
+            Some(json!({ "command": name, "status": "ok", "data": data }))
+        })
+    }
+
+    fn commander(device: &FakeDevice) -> OpenIrisCommander {
+        let camera = OpenIrisCamera::init();
+        camera.link.io().attach(Box::new(device.clone()));
+        camera.commander().with_timeout(Duration::from_millis(200))
+    }
+
+    #[test]
+    fn test_provision() {
+        let device = tracker("hunter22");
+        device.set_streaming(true);
+
+        let source = WifiProvisioning::new("home", "hunter22")
+            .with_hostname("left-eye")
+            .provision(&commander(&device))
+            .expect("provisioning should succeed");
+        assert_eq!(source.as_str(), "http://192.168.1.42:81/");
+
+        let received: Vec<_> = device
+            .commands()
+            .iter()
+            .map(|command| command["command"].clone())
+            .filter(|name| name != "get_wifi_status")
+            .collect();
+        assert_eq!(
+            received,
+            [
+                "ping",
+                "set_wifi",
+                "set_mdns",
+                "connect_wifi",
+                "switch_mode",
+                "restart_device"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wrong_password() {
+        let device = tracker("hunter22");
+        let result = WifiProvisioning::new("home", "hunter2").provision(&commander(&device));
+        assert_eq!(result.err(), Some(ProvisioningError::WrongPassword));
+    }
+
+    #[test]
+    fn test_unsupported_firmware() {
+        // streams frames but ignores commands
+        let device = FakeDevice::new(|_| None);
+        device.set_streaming(true);
+        let result = WifiProvisioning::new("home", "hunter22").provision(&commander(&device));
+        assert_eq!(result.err(), Some(ProvisioningError::UnsupportedFirmware));
+
+        let device = FakeDevice::new(|command| {
+            let name = command["command"].as_str()?;
+            Some(match name {
+                "ping" => json!({ "command": name, "status": "ok" }),
+                _ => json!({ "command": name, "status": "error", "error": "Unknown command" }),
+            })
+        });
+        let result = WifiProvisioning::new("home", "hunter22").provision(&commander(&device));
+        assert_eq!(result.err(), Some(ProvisioningError::UnsupportedFirmware));
+    }
+}
@@ -0,0 +1,370 @@
+use std::{
+    sync::{Arc, Mutex, mpsc},
+    time::Duration,
+};
+
+use log::{info, trace, warn};
+use serialport::SerialPortInfo;
+
+use super::OpenIrisDevice;
+use crate::{Camera, CameraControl, CameraState, events::EventBus};
+
+/// A tracker showing up in or disappearing from the serial port list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceEvent {
+    DeviceAdded(OpenIrisDevice),
+    DeviceRemoved(OpenIrisDevice),
+}
+
+type PortLister = Box<dyn Fn() -> serialport::Result<Vec<SerialPortInfo>> + Send>;
+
+/// A camera handed to [`SerialWatcher::attach`]
+#[derive(Debug)]
+struct Attached {
+    camera: CameraControl,
+    /// Learned once the port of the camera shows up in the port list
+    serial_number: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    devices: Mutex<Vec<OpenIrisDevice>>,
+    cameras: Mutex<Vec<Attached>>,
+    events: EventBus<DeviceEvent>,
+}
+
+/// Watches the serial port list for OpenIris trackers being plugged in or
+/// unplugged
+/// - Polls in a background thread, stopped once the watcher is dropped
+/// - Attached cameras follow their tracker across unplugging, even if it comes
+///   back on a different port
+#[derive(Debug)]
+pub struct SerialWatcher {
+    shared: Arc<Shared>,
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl SerialWatcher {
+    /// Starts polling the serial port list every `interval`
+    pub fn spawn(interval: Duration) -> SerialWatcher {
+        Self::spawn_with(interval, Box::new(serialport::available_ports))
+    }
+
+    fn spawn_with(interval: Duration, list_ports: PortLister) -> SerialWatcher {
+        let shared = Arc::new(Shared::default());
+        // devices present from the start are not reported as added
+        *shared.devices.lock().expect("devices lock poisoned") =
+            list_devices(&list_ports).unwrap_or_default();
+
+        let (stop, stop_rx) = mpsc::channel();
+        let thread = {
+            let shared = shared.clone();
+            std::thread::spawn(move || {
+                while let Err(mpsc::RecvTimeoutError::Timeout) = stop_rx.recv_timeout(interval) {
+                    poll(&shared, &list_ports);
+                }
+                trace!("serial watcher stopped");
+            })
+        };
+
+        SerialWatcher {
+            shared,
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    /// Subscribes to trackers being added or removed
+    /// - Dropping the receiver unsubscribes
+    pub fn subscribe(&self) -> mpsc::Receiver<DeviceEvent> {
+        self.shared.events.subscribe()
+    }
+
+    /// Returns the trackers found during the last poll
+    pub fn devices(&self) -> Vec<OpenIrisDevice> {
+        self.shared
+            .devices
+            .lock()
+            .expect("devices lock poisoned")
+            .clone()
+    }
+
+    /// Keeps the camera connected to its tracker
+    /// - The tracker is identified by its USB serial number once the source of
+    ///   the camera shows up in the port list
+    /// - The camera is disconnected once its tracker is unplugged and connected
+    ///   to the new port once it comes back, see [`CameraControl`]
+    /// - Cameras disconnected through [`Camera::disconnect`] are left alone
+    /// - The camera is never locked, dropped cameras are forgotten
+    pub fn attach(&self, camera: &Camera) {
+        self.shared
+            .cameras
+            .lock()
+            .expect("cameras lock poisoned")
+            .push(Attached {
+                camera: camera.control(),
+                serial_number: None,
+            });
+    }
+}
+
+impl Drop for SerialWatcher {
+    fn drop(&mut self) {
+        // closing the channel wakes up the thread
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            thread.join().expect("serial watcher thread has panicked");
+        }
+    }
+}
+
+fn list_devices(list_ports: &PortLister) -> Option<Vec<OpenIrisDevice>> {
+    let ports = match list_ports() {
+        Ok(ports) => ports,
+        Err(e) => {
+            warn!("failed to list serial ports: {e}");
+            return None;
+        }
+    };
+
+    let mut devices: Vec<_> = ports.iter().filter_map(OpenIrisDevice::identify).collect();
+    devices.sort_by(|a, b| a.port.cmp(&b.port));
+    Some(devices)
+}
+
+fn poll(shared: &Shared, list_ports: &PortLister) {
+    let Some(current) = list_devices(list_ports) else {
+        return;
+    };
+
+    let previous = std::mem::replace(
+        &mut *shared.devices.lock().expect("devices lock poisoned"),
+        current.clone(),
+    );
+    let removed: Vec<_> = previous
+        .iter()
+        .filter(|device| !current.contains(device))
+        .collect();
+    let added: Vec<_> = current
+        .iter()
+        .filter(|device| !previous.contains(device))
+        .collect();
+
+    // cameras connected since the last poll are only known by their port
+    let cameras = {
+        let mut cameras = shared.cameras.lock().expect("cameras lock poisoned");
+        cameras.retain(|attached| !attached.camera.is_dropped());
+        learn_serial_numbers(&mut cameras, &previous);
+        cameras
+            .iter()
+            .filter_map(|attached| Some((attached.camera.clone(), attached.serial_number.clone()?)))
+            .collect::<Vec<_>>()
+    };
+    let owned_by = |device: &OpenIrisDevice| {
+        cameras
+            .iter()
+            .filter(|(_, serial_number)| device.serial_number.as_ref() == Some(serial_number))
+            .map(|(camera, _)| camera.clone())
+            .collect::<Vec<_>>()
+    };
+
+    for device in removed {
+        info!("tracker removed from {}", device.port);
+        for camera in owned_by(device) {
+            if camera.source().as_deref() == Some(&device.port) {
+                // stops the handler thread from failing on a vanished port,
+                // errors only mean it was not connected in the first place
+                let _ = camera.request_disconnect();
+            }
+        }
+        shared
+            .events
+            .emit(DeviceEvent::DeviceRemoved(device.clone()));
+    }
+
+    for device in added {
+        info!("tracker added at {}", device.port);
+        for camera in owned_by(device) {
+            let connected = camera.status() != CameraState::Disconnected
+                && camera.source().as_deref() == Some(&device.port);
+            if connected {
+                continue;
+            }
+
+            match camera.request_switch(device.port.clone()) {
+                Ok(()) => info!("reconnecting camera to {}", device.port),
+                Err(e) => trace!("not reconnecting camera to {}: {e:?}", device.port),
+            }
+        }
+        shared.events.emit(DeviceEvent::DeviceAdded(device.clone()));
+    }
+}
+
+fn learn_serial_numbers(cameras: &mut [Attached], devices: &[OpenIrisDevice]) {
+    for attached in cameras.iter_mut() {
+        let Some(source) = attached.camera.source() else {
+            continue;
+        };
+
+        let serial_number = devices
+            .iter()
+            .find(|device| device.port == source)
+            .and_then(|device| device.serial_number.clone());
+        if serial_number.is_some() {
+            attached.serial_number = serial_number;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use serialport::{SerialPortType, UsbPortInfo};
+
+    use super::*;
+    use crate::{CameraHandler, Frame, NoOpCamera};
+
+    /// Backend of a tracker that stopped streaming, consumers wait forever
+    #[derive(Debug)]
+    struct SilentCamera;
+
+    impl CameraHandler for SilentCamera {
+        fn init() -> Self
+        where
+            Self: Sized,
+        {
+            SilentCamera
+        }
+
+        fn get_frame(&mut self) -> Result<Frame, CameraState> {
+            std::thread::sleep(Duration::from_millis(5));
+            Err(CameraState::Timeout)
+        }
+
+        fn connect(&mut self, _source: String) -> Result<(), CameraState> {
+            Ok(())
+        }
+
+        fn disconnect(&mut self) {}
+    }
+
+    fn tracker(port_name: &str, serial_number: &str) -> SerialPortInfo {
+        SerialPortInfo {
+            port_name: port_name.to_string(),
+            port_type: SerialPortType::UsbPort(UsbPortInfo {
+                vid: 0x303A,
+                pid: 0x1001,
+                serial_number: Some(serial_number.to_string()),
+                manufacturer: None,
+                product: None,
+            }),
+        }
+    }
+
+    fn watch(ports: &Arc<Mutex<Vec<SerialPortInfo>>>) -> SerialWatcher {
+        let ports = ports.clone();
+        SerialWatcher::spawn_with(
+            Duration::from_millis(5),
+            Box::new(move || Ok(ports.lock().unwrap().clone())),
+        )
+    }
+
+    fn next_event(events: &mpsc::Receiver<DeviceEvent>) -> DeviceEvent {
+        events
+            .recv_timeout(Duration::from_secs(1))
+            .expect("watcher should report changes")
+    }
+
+    /// Waits for the capture thread to carry out a request of the watcher
+    fn wait_for(camera: &Camera, state: CameraState) {
+        let deadline = Instant::now() + Duration::from_secs(1);
+        while camera.status() != state {
+            assert!(Instant::now() < deadline, "camera never became {state:?}");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_reconnects_on_new_port() {
+        let ports = Arc::new(Mutex::new(vec![
+            tracker("COM13", "left-eye"),
+            tracker("COM14", "right-eye"),
+        ]));
+        let watcher = watch(&ports);
+        let events = watcher.subscribe();
+        assert_eq!(watcher.devices().len(), 2);
+
+        let mut camera = Camera::from_camera_handler(Box::new(NoOpCamera::init()), 30);
+        camera
+            .connect("COM13".into())
+            .expect("no-op camera should connect");
+        watcher.attach(&camera);
+
+        // unplugging disconnects the camera
+        ports.lock().unwrap().remove(0);
+        assert!(matches!(
+            next_event(&events),
+            DeviceEvent::DeviceRemoved(device) if device.port == "COM13"
+        ));
+        wait_for(&camera, CameraState::Disconnected);
+
+        // the same tracker comes back on another port
+        ports.lock().unwrap().push(tracker("COM15", "left-eye"));
+        assert!(matches!(
+            next_event(&events),
+            DeviceEvent::DeviceAdded(device) if device.port == "COM15"
+        ));
+        wait_for(&camera, CameraState::Connected);
+        assert_eq!(camera.source().as_deref(), Some("COM15"));
+        camera.get_frame().expect("frames should flow again");
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+    }
+
+    #[test]
+    fn test_unplug_wakes_blocked_consumer() {
+        let ports = Arc::new(Mutex::new(vec![tracker("COM13", "left-eye")]));
+        let watcher = watch(&ports);
+        let events = watcher.subscribe();
+
+        let camera = Arc::new(Mutex::new(Camera::from_camera_handler(
+            Box::new(SilentCamera),
+            30,
+        )));
+        {
+            let mut camera = camera.lock().unwrap();
+            camera
+                .connect("COM13".into())
+                .expect("silent camera should connect");
+            watcher.attach(&camera);
+        }
+
+        // the consumer holds the lock while waiting for a frame that never
+        // arrives
+        let consumer = {
+            let camera = camera.clone();
+            std::thread::spawn(move || camera.lock().unwrap().get_frame().err())
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!consumer.is_finished());
+
+        ports.lock().unwrap().clear();
+        assert!(matches!(next_event(&events), DeviceEvent::DeviceRemoved(_)));
+        assert_eq!(
+            consumer.join().expect("consumer should be woken up"),
+            Some(CameraState::Disconnected)
+        );
+
+        ports.lock().unwrap().push(tracker("COM15", "left-eye"));
+        assert!(matches!(next_event(&events), DeviceEvent::DeviceAdded(_)));
+        let mut camera = camera.lock().unwrap();
+        wait_for(&camera, CameraState::Timeout);
+        assert_eq!(camera.source().as_deref(), Some("COM15"));
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+    }
+}
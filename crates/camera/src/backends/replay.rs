@@ -0,0 +1,482 @@
+// Plays back recordings written by `crate::Recorder`, e.g. to reproduce
+// tracking issues without the device that caused them.
+
+use std::{
+    str::FromStr,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use log::{debug, info};
+
+use crate::{CameraHandler, CameraState, Frame, Recording};
+
+// how long a paused replay waits for being resumed before repeating the
+// current frame, keeps the camera thread responsive to disconnects
+const PAUSE_POLL: Duration = Duration::from_millis(50);
+
+/// Pace at which a recording is played back
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplayMode {
+    /// Keeps the time between frames as recorded
+    #[default]
+    OriginalTiming,
+    /// Delivers frames as fast as they are requested, limited only by the
+    /// target frame rate of [`crate::Camera`]
+    AsFastAsPossible,
+    /// Delivers frames at the given rate, regardless of their recorded timing
+    FixedFps(u16),
+}
+
+impl FromStr for ReplayMode {
+    type Err = ();
+
+    /// Parses `original`, `fast` or a frame rate, e.g. `60`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "original" => Ok(ReplayMode::OriginalTiming),
+            "fast" => Ok(ReplayMode::AsFastAsPossible),
+            fps => match fps.parse() {
+                Ok(0) | Err(_) => Err(()),
+                Ok(fps) => Ok(ReplayMode::FixedFps(fps)),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SeekTarget {
+    Frame(usize),
+    Time(Duration),
+}
+
+#[derive(Debug, Default)]
+struct Playback {
+    mode: ReplayMode,
+    looping: bool,
+    paused: bool,
+    seek: Option<SeekTarget>,
+    // index of the next frame
+    position: usize,
+    frames: usize,
+    finished: bool,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    playback: Mutex<Playback>,
+    resumed: Condvar,
+}
+
+impl Shared {
+    fn playback(&self) -> MutexGuard<'_, Playback> {
+        self.playback.lock().expect("playback lock poisoned")
+    }
+}
+
+/// Controls a [`ReplayCamera`] after it was handed to [`crate::Camera`]
+/// - Obtained through [`ReplayCamera::control`], clones control the same
+///   replay
+#[derive(Debug, Clone)]
+pub struct ReplayControl {
+    shared: Arc<Shared>,
+}
+
+impl ReplayControl {
+    /// Holds playback at the current frame
+    /// - The camera keeps delivering the last frame while paused
+    pub fn pause(&self) {
+        self.shared.playback().paused = true;
+    }
+
+    pub fn resume(&self) {
+        self.shared.playback().paused = false;
+        self.shared.resumed.notify_all();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.shared.playback().paused
+    }
+
+    /// Continues playback at the frame with the given index
+    /// - Restarts a replay that reached the end
+    /// - Indices past the end are moved to the last frame
+    pub fn seek(&self, frame: usize) {
+        self.shared.playback().seek = Some(SeekTarget::Frame(frame));
+    }
+
+    /// Continues playback at the last frame recorded at or before the given
+    /// time, relative to the first frame
+    pub fn seek_to(&self, time: Duration) {
+        self.shared.playback().seek = Some(SeekTarget::Time(time));
+    }
+
+    /// Returns the index of the next frame to be delivered
+    pub fn position(&self) -> usize {
+        self.shared.playback().position
+    }
+
+    /// Returns the amount of frames in the recording, `0` until connected
+    pub fn frames(&self) -> usize {
+        self.shared.playback().frames
+    }
+
+    /// Whether the end of a recording was reached without looping
+    pub fn is_finished(&self) -> bool {
+        self.shared.playback().finished
+    }
+
+    pub fn set_mode(&self, mode: ReplayMode) {
+        self.shared.playback().mode = mode;
+    }
+
+    pub fn set_looping(&self, looping: bool) {
+        self.shared.playback().looping = looping;
+    }
+}
+
+/// Anchor of [`ReplayMode::OriginalTiming`], reset whenever playback jumps
+#[derive(Debug, Clone, Copy)]
+struct Clock {
+    started: Instant,
+    offset: Duration,
+}
+
+/// Plays back a recording, e.g. `replay:///recordings/left.etvr?mode=fast`
+/// - Reports [`CameraState::EndOfStream`] once the end is reached, unless
+///   looping
+/// - Frames keep the sequence numbers they were recorded with
+/// - Frames are timestamped when delivered, the recorded time is available
+///   through [`Recording::timestamp`]
+#[derive(Debug)]
+pub struct ReplayCamera {
+    recording: Option<Recording>,
+    shared: Arc<Shared>,
+    clock: Option<Clock>,
+    last_frame: Option<Instant>,
+}
+
+impl ReplayCamera {
+    pub fn with_mode(self, mode: ReplayMode) -> Self {
+        self.shared.playback().mode = mode;
+        self
+    }
+
+    /// Starts over at the first frame after reaching the end
+    pub fn with_looping(self, looping: bool) -> Self {
+        self.shared.playback().looping = looping;
+        self
+    }
+
+    /// Returns a handle for pausing, seeking and changing the mode during
+    /// playback
+    pub fn control(&self) -> ReplayControl {
+        ReplayControl {
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// Waits until the frame recorded at the given time is due
+    fn pace(&mut self, mode: ReplayMode, timestamp: Duration) {
+        let due = match mode {
+            ReplayMode::OriginalTiming => {
+                let clock = *self.clock.get_or_insert(Clock {
+                    started: Instant::now(),
+                    offset: timestamp,
+                });
+                Some(clock.started + timestamp.saturating_sub(clock.offset))
+            }
+            ReplayMode::FixedFps(fps) => self
+                .last_frame
+                .map(|last| last + Duration::from_secs(1) / fps.max(1) as u32),
+            ReplayMode::AsFastAsPossible => None,
+        };
+
+        if let Some(due) = due {
+            std::thread::sleep(due.saturating_duration_since(Instant::now()));
+        }
+        self.last_frame = Some(Instant::now());
+    }
+}
+
+impl CameraHandler for ReplayCamera {
+    fn init() -> Self
+    where
+        Self: Sized,
+    {
+        Self {
+            recording: None,
+            shared: Arc::default(),
+            clock: None,
+            last_frame: None,
+        }
+    }
+
+    fn get_frame(&mut self) -> Result<Frame, CameraState> {
+        let Some(recording) = self.recording.as_ref() else {
+            return Err(CameraState::Disconnected);
+        };
+
+        let shared = self.shared.clone();
+        let mut playback = shared.playback();
+
+        if playback.paused {
+            playback = shared
+                .resumed
+                .wait_timeout_while(playback, PAUSE_POLL, |playback| playback.paused)
+                .expect("playback lock poisoned")
+                .0;
+            // timing starts over once resumed
+            self.clock = None;
+        }
+
+        if let Some(target) = playback.seek.take() {
+            let index = match target {
+                SeekTarget::Frame(frame) => frame,
+                SeekTarget::Time(time) => recording.position(time),
+            };
+            // connecting ensures the recording is not empty
+            let index = index.min(recording.len() - 1);
+            // a paused replay shows the frame it was moved to
+            playback.position = if playback.paused { index + 1 } else { index };
+            playback.finished = false;
+            self.clock = None;
+        }
+
+        if playback.paused {
+            // repeat the last delivered frame
+            let index = playback.position.saturating_sub(1);
+            drop(playback);
+            return self.read_frame(index);
+        }
+
+        if playback.position >= recording.len() {
+            if !playback.looping {
+                if !playback.finished {
+                    info!("replay reached the end of the recording");
+                    playback.finished = true;
+                }
+                return Err(CameraState::EndOfStream);
+            }
+
+            debug!("replay starting over");
+            playback.position = 0;
+            self.clock = None;
+        }
+
+        let index = playback.position;
+        playback.position += 1;
+        let mode = playback.mode;
+        drop(playback);
+
+        let timestamp = recording.timestamp(index).unwrap_or_default();
+        self.pace(mode, timestamp);
+        self.read_frame(index)
+    }
+
+    fn keeps_sequence(&self) -> bool {
+        true
+    }
+
+    fn connect(&mut self, source: String) -> Result<(), CameraState> {
+        info!("opening recording {source}");
+        let mut recording = Recording::open(&source)?;
+        if recording.is_empty() {
+            return Err(CameraState::Error(format!("Recording {source} is empty")));
+        }
+
+        // make sure the frames are readable
+        recording.read_frame(0)?;
+        debug!(
+            "opened recording of {} frames from {:?}",
+            recording.len(),
+            recording.metadata().source
+        );
+
+        let mut playback = self.shared.playback();
+        playback.frames = recording.len();
+        playback.position = 0;
+        playback.finished = false;
+        drop(playback);
+
+        self.recording = Some(recording);
+        self.clock = None;
+        self.last_frame = None;
+
+        Ok(())
+    }
+
+    fn disconnect(&mut self) {
+        self.recording = None;
+    }
+}
+
+impl ReplayCamera {
+    fn read_frame(&mut self, index: usize) -> Result<Frame, CameraState> {
+        self.recording
+            .as_mut()
+            .ok_or(CameraState::Disconnected)?
+            .read_frame(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+    use crate::{Camera, PixelFormat, ReconnectPolicy, Recorder};
+
+    /// Records frames `0..6` filled with their index, 20 ms apart
+    fn record(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("etvr-{}-{name}", std::process::id()));
+        let mut recorder = Recorder::create(&path).unwrap();
+
+        let start = Instant::now();
+        for i in 0..6u8 {
+            let mut frame = Frame::packed(vec![i; 16], 4, 4, PixelFormat::Gray8).unwrap();
+            frame.timestamp = start + Duration::from_millis(i as u64 * 20);
+            // as if recording started while the camera was running
+            frame.sequence = 100 + i as u64;
+            recorder.write(&frame).unwrap();
+        }
+        recorder.finish().unwrap();
+
+        path
+    }
+
+    fn replay(path: &Path, mode: ReplayMode) -> ReplayCamera {
+        let mut camera = ReplayCamera::init().with_mode(mode);
+        camera
+            .connect(path.to_string_lossy().into_owned())
+            .expect("recording should open");
+        camera
+    }
+
+    fn next(camera: &mut ReplayCamera) -> u8 {
+        camera.get_frame().expect("frame should be replayed").data[0]
+    }
+
+    #[test]
+    fn test_modes() {
+        let path = record("modes.etvr");
+
+        let mut camera = replay(&path, ReplayMode::AsFastAsPossible);
+        let replayed: Vec<_> = (0..6).map(|_| next(&mut camera)).collect();
+        assert_eq!(replayed, [0, 1, 2, 3, 4, 5]);
+        assert_eq!(camera.get_frame().err(), Some(CameraState::EndOfStream));
+        assert!(camera.control().is_finished());
+
+        let mut camera = replay(&path, ReplayMode::OriginalTiming);
+        let start = Instant::now();
+        for _ in 0..6 {
+            next(&mut camera);
+        }
+        assert!(start.elapsed() >= Duration::from_millis(100));
+
+        let mut camera = replay(&path, ReplayMode::FixedFps(200));
+        let start = Instant::now();
+        for _ in 0..6 {
+            next(&mut camera);
+        }
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(25) && elapsed < Duration::from_millis(100));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_control() {
+        let path = record("control.etvr");
+        let mut camera = replay(&path, ReplayMode::AsFastAsPossible).with_looping(true);
+        let control = camera.control();
+        assert_eq!(control.frames(), 6);
+
+        control.seek(4);
+        let replayed: Vec<_> = (0..4).map(|_| next(&mut camera)).collect();
+        assert_eq!(replayed, [4, 5, 0, 1]);
+
+        control.seek_to(Duration::from_millis(70));
+        assert_eq!(next(&mut camera), 3);
+
+        control.pause();
+        assert_eq!(next(&mut camera), 3);
+        assert_eq!(next(&mut camera), 3);
+        control.seek(1);
+        assert_eq!(next(&mut camera), 1);
+        control.resume();
+        assert_eq!(next(&mut camera), 2);
+        assert_eq!(control.position(), 3);
+
+        // seeking past the end shows the last frame
+        control.pause();
+        control.seek(100);
+        assert_eq!(next(&mut camera), 5);
+        assert_eq!(next(&mut camera), 5);
+        control.resume();
+        assert_eq!(next(&mut camera), 0);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_end_of_stream() {
+        let path = record("end.etvr");
+        let handler = replay(&path, ReplayMode::AsFastAsPossible);
+        let control = handler.control();
+        let mut camera = Camera::from_camera_handler(Box::new(handler), 1000);
+        camera
+            .connect(path.to_string_lossy().into_owned())
+            .expect("recording should open");
+        // reaching the end is no failure, nothing is reconnected
+        camera.set_reconnect_policy(Some(ReconnectPolicy {
+            error_threshold: 1,
+            ..ReconnectPolicy::default()
+        }));
+
+        let sequences: Vec<_> = std::iter::from_fn(|| camera.get_frame().ok())
+            .map(|frame| frame.sequence)
+            .collect();
+        assert_eq!(sequences, [100, 101, 102, 103, 104, 105]);
+        assert_eq!(camera.status(), CameraState::EndOfStream);
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert_eq!(camera.status(), CameraState::EndOfStream);
+        assert!(control.is_finished());
+        assert_eq!(camera.get_frame().err(), Some(CameraState::Disconnected));
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_open_by_uri() {
+        let path = record("uri.etvr");
+        let uri = format!("replay://{}?mode=fast", path.display());
+        let mut camera = Camera::open(&uri, 1000).expect("recording should open");
+
+        let replayed: Vec<_> = (0..6)
+            .map(|_| {
+                camera
+                    .get_frame_timeout(Duration::from_secs(1))
+                    .unwrap()
+                    .data[0]
+            })
+            .collect();
+        assert_eq!(replayed, [0, 1, 2, 3, 4, 5]);
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+
+        assert_eq!(
+            Camera::open("replay:///does/not/exist.etvr", 30).err(),
+            Some(CameraState::InvalidSource)
+        );
+        assert_eq!(
+            Camera::open(&format!("replay://{}?mode=slow", path.display()), 30).err(),
+            Some(CameraState::InvalidSource)
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
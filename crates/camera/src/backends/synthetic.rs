@@ -0,0 +1,612 @@
+// Procedural IR eye images with known pupil position, for exercising the
+// tracking pipeline without hardware. Rendering is deterministic: the same
+// eye renders the same bytes for the same frame index.
+
+use std::{f32::consts::TAU, time::Duration};
+
+use log::debug;
+
+use crate::{CameraHandler, CameraSource, CameraState, Frame, PixelFormat};
+
+// gray levels roughly matching an IR lit eye
+const SKIN: f32 = 150.0;
+const SCLERA: f32 = 205.0;
+const IRIS: f32 = 95.0;
+const PUPIL: f32 = 12.0;
+const GLINT: f32 = 255.0;
+
+// geometry relative to the image size
+const EYE_HALF_WIDTH: f32 = 0.45;
+const EYE_HALF_HEIGHT: f32 = 0.3;
+const IRIS_RADIUS: f32 = 0.22;
+// how far the iris travels from the center at full gaze, relative to the eye
+const GAZE_RANGE: f32 = 0.45;
+const GLINT_RADIUS: f32 = 2.5;
+const MOTION_BLUR_SAMPLES: u32 = 4;
+// duration of blinks requested through the source
+const BLINK_DURATION: Duration = Duration::from_millis(150);
+
+/// Pose of the eye at a point in time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EyeState {
+    /// Horizontal gaze from `-1.0` (left in the image) to `1.0`
+    pub gaze_x: f32,
+    /// Vertical gaze from `-1.0` (up in the image) to `1.0`
+    pub gaze_y: f32,
+    /// Pupil radius relative to the iris, from `0.0` to `1.0`
+    pub pupil_size: f32,
+    /// `1.0` for a fully open eye, `0.0` for a closed one
+    pub openness: f32,
+}
+
+impl Default for EyeState {
+    fn default() -> Self {
+        Self {
+            gaze_x: 0.0,
+            gaze_y: 0.0,
+            pupil_size: 0.4,
+            openness: 1.0,
+        }
+    }
+}
+
+impl EyeState {
+    fn lerp(&self, other: &EyeState, t: f32) -> EyeState {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        EyeState {
+            gaze_x: mix(self.gaze_x, other.gaze_x),
+            gaze_y: mix(self.gaze_y, other.gaze_y),
+            pupil_size: mix(self.pupil_size, other.pupil_size),
+            openness: mix(self.openness, other.openness),
+        }
+    }
+}
+
+/// Movement of the eye over time
+#[derive(Debug, Clone, PartialEq)]
+pub enum GazeScript {
+    Fixed(EyeState),
+    /// Circles around the center once per period, starting on the right
+    Circle {
+        radius: f32,
+        period: Duration,
+    },
+    /// Interpolates linearly between `(time, state)` pairs sorted by time,
+    /// holding the first and last state outside of them
+    Keyframes(Vec<(Duration, EyeState)>),
+}
+
+impl Default for GazeScript {
+    fn default() -> Self {
+        GazeScript::Circle {
+            radius: 0.6,
+            period: Duration::from_secs(4),
+        }
+    }
+}
+
+impl GazeScript {
+    /// Returns the scripted state at the given time
+    pub fn state_at(&self, time: Duration) -> EyeState {
+        match self {
+            GazeScript::Fixed(state) => *state,
+            GazeScript::Circle { radius, period } => {
+                let angle = TAU * (time.as_secs_f32() / period.as_secs_f32().max(f32::EPSILON));
+                EyeState {
+                    gaze_x: radius * angle.cos(),
+                    gaze_y: radius * angle.sin(),
+                    ..EyeState::default()
+                }
+            }
+            GazeScript::Keyframes(keyframes) => {
+                let next = keyframes.partition_point(|(at, _)| *at <= time);
+                match (
+                    next.checked_sub(1).map(|i| &keyframes[i]),
+                    keyframes.get(next),
+                ) {
+                    (Some((from, a)), Some((to, b))) => {
+                        let t = (time - *from).as_secs_f32() / (*to - *from).as_secs_f32();
+                        a.lerp(b, t)
+                    }
+                    (Some((_, state)), None) | (None, Some((_, state))) => *state,
+                    (None, None) => EyeState::default(),
+                }
+            }
+        }
+    }
+}
+
+/// Where the eye is in a rendered frame, in pixels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GroundTruth {
+    /// Time of the frame relative to the first one
+    pub time: Duration,
+    pub state: EyeState,
+    pub pupil_x: f32,
+    pub pupil_y: f32,
+    pub pupil_radius: f32,
+    /// Whether the pupil center is between the eyelids
+    pub pupil_visible: bool,
+}
+
+/// Renders the frames of a [`SyntheticEyeCamera`]
+/// - Time advances by one frame interval per frame, independent of how fast
+///   frames are requested
+/// - Frame `n` is the frame with sequence `n` of a freshly connected
+///   [`crate::Camera`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntheticEye {
+    width: u32,
+    height: u32,
+    frame_rate: u16,
+    script: GazeScript,
+    // interval and duration
+    blinks: Option<(Duration, Duration)>,
+    noise: u8,
+    motion_blur: Option<Duration>,
+    glints: bool,
+    seed: u64,
+}
+
+impl Default for SyntheticEye {
+    /// A 240x240 eye circling at 60 fps, the size of OpenIris frames
+    fn default() -> Self {
+        Self {
+            width: 240,
+            height: 240,
+            frame_rate: 60,
+            script: GazeScript::default(),
+            blinks: None,
+            noise: 0,
+            motion_blur: None,
+            glints: true,
+            seed: 0,
+        }
+    }
+}
+
+impl SyntheticEye {
+    /// Reads the settings from the query of the source, unset ones keep their
+    /// defaults
+    /// - `width` and `height`: frame size in pixels
+    /// - `fps`: frame rate of the scripted time
+    /// - `noise`: noise amplitude in gray levels
+    /// - `blur`: exposure time for motion blur in milliseconds
+    /// - `blink`: blink interval in milliseconds
+    /// - `seed`: seed of the noise
+    ///
+    /// Returns [`CameraState::InvalidSource`] for malformed values.
+    pub fn from_source(source: &CameraSource) -> Result<SyntheticEye, CameraState> {
+        let mut eye = SyntheticEye::default();
+
+        if let Some(width) = source.parse_param("width")? {
+            eye.width = width;
+        }
+        if let Some(height) = source.parse_param("height")? {
+            eye.height = height;
+        }
+        if let Some(frame_rate) = source.parse_param("fps")? {
+            eye = eye.with_frame_rate(frame_rate);
+        }
+        if let Some(noise) = source.parse_param("noise")? {
+            eye.noise = noise;
+        }
+        if let Some(blur) = source.parse_param("blur")? {
+            eye.motion_blur = Some(Duration::from_millis(blur));
+        }
+        if let Some(interval) = source.parse_param("blink")? {
+            eye.blinks = Some((Duration::from_millis(interval), BLINK_DURATION));
+        }
+        if let Some(seed) = source.parse_param("seed")? {
+            eye.seed = seed;
+        }
+
+        Ok(eye)
+    }
+
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn with_frame_rate(mut self, frame_rate: u16) -> Self {
+        self.frame_rate = frame_rate.max(1);
+        self
+    }
+
+    pub fn with_script(mut self, script: GazeScript) -> Self {
+        self.script = script;
+        self
+    }
+
+    /// Closes the eye for the given duration once per interval, on top of
+    /// the scripted openness
+    pub fn with_blinks(mut self, interval: Duration, duration: Duration) -> Self {
+        self.blinks = Some((interval, duration));
+        self
+    }
+
+    /// Adds sensor noise of up to the given amount of gray levels
+    pub fn with_noise(mut self, amplitude: u8) -> Self {
+        self.noise = amplitude;
+        self
+    }
+
+    /// Blends the eye movement during the given exposure time, centered on
+    /// the frame time
+    pub fn with_motion_blur(mut self, exposure: Duration) -> Self {
+        self.motion_blur = Some(exposure);
+        self
+    }
+
+    /// Toggles the reflections of the IR LEDs, enabled by default
+    pub fn with_glints(mut self, glints: bool) -> Self {
+        self.glints = glints;
+        self
+    }
+
+    /// Seeds the noise, eyes with the same seed render identical frames
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Returns the time of the frame relative to the first one
+    pub fn frame_time(&self, index: u64) -> Duration {
+        Duration::from_secs(index) / self.frame_rate as u32
+    }
+
+    /// Returns the pose of the eye at the given time, including blinks
+    pub fn state_at(&self, time: Duration) -> EyeState {
+        let mut state = self.script.state_at(time);
+
+        if let Some((interval, duration)) = self.blinks {
+            let phase = time.as_secs_f32() % interval.as_secs_f32().max(f32::EPSILON);
+            if phase < duration.as_secs_f32() {
+                // closes and opens again smoothly
+                let closure = 0.5 - 0.5 * (TAU * phase / duration.as_secs_f32()).cos();
+                state.openness *= 1.0 - closure;
+            }
+        }
+
+        state
+    }
+
+    /// Returns where the pupil is in the frame with the given index
+    pub fn ground_truth(&self, index: u64) -> GroundTruth {
+        let time = self.frame_time(index);
+        let state = self.state_at(time);
+        let geometry = Geometry::new(self, &state);
+
+        GroundTruth {
+            time,
+            state,
+            pupil_x: geometry.iris.0,
+            pupil_y: geometry.iris.1,
+            pupil_radius: geometry.pupil_radius,
+            pupil_visible: geometry.opening(geometry.iris.0, geometry.iris.1) > 0.5,
+        }
+    }
+
+    /// Renders the frame with the given index as [`PixelFormat::Gray8`]
+    pub fn render(&self, index: u64) -> Frame {
+        let time = self.frame_time(index);
+        let samples: Vec<_> = match self.motion_blur {
+            Some(exposure) => (0..MOTION_BLUR_SAMPLES)
+                .map(|i| {
+                    let offset = exposure.as_secs_f32()
+                        * (i as f32 / (MOTION_BLUR_SAMPLES - 1) as f32 - 0.5);
+                    let time = Duration::from_secs_f32((time.as_secs_f32() + offset).max(0.0));
+                    Geometry::new(self, &self.state_at(time))
+                })
+                .collect(),
+            None => vec![Geometry::new(self, &self.state_at(time))],
+        };
+
+        let mut rng = Rng::new(self.seed ^ index.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        let noise = self.noise as f32;
+        let mut data = Vec::with_capacity((self.width * self.height) as usize);
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+                let mut value = samples
+                    .iter()
+                    .map(|geometry| geometry.shade(x, y))
+                    .sum::<f32>()
+                    / samples.len() as f32;
+                if self.noise > 0 {
+                    value += noise * (rng.next_f32() - rng.next_f32());
+                }
+                data.push(value.round().clamp(0.0, 255.0) as u8);
+            }
+        }
+
+        Frame::packed(data, self.width, self.height, PixelFormat::Gray8)
+            .expect("gray8 rows always fit into the stride")
+    }
+}
+
+/// Positions and sizes of the eye features in pixels
+struct Geometry {
+    center: (f32, f32),
+    half_width: f32,
+    lid_height: f32,
+    iris: (f32, f32),
+    iris_radius: f32,
+    pupil_radius: f32,
+    glints: Vec<(f32, f32)>,
+}
+
+impl Geometry {
+    fn new(eye: &SyntheticEye, state: &EyeState) -> Geometry {
+        let (width, height) = (eye.width as f32, eye.height as f32);
+        let center = (width / 2.0, height / 2.0);
+        let half_width = EYE_HALF_WIDTH * width;
+        let half_height = EYE_HALF_HEIGHT * height;
+        let iris_radius = IRIS_RADIUS * width.min(height);
+
+        let iris = (
+            center.0 + state.gaze_x.clamp(-1.0, 1.0) * GAZE_RANGE * half_width,
+            center.1 + state.gaze_y.clamp(-1.0, 1.0) * GAZE_RANGE * half_height,
+        );
+        // the corneal reflections move at about half the speed of the pupil
+        let glints = if eye.glints {
+            [-0.35, 0.35]
+                .iter()
+                .map(|side| {
+                    (
+                        (center.0 + iris.0) / 2.0 + side * iris_radius,
+                        (center.1 + iris.1) / 2.0 + 0.35 * iris_radius,
+                    )
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Geometry {
+            center,
+            half_width,
+            lid_height: half_height * state.openness.clamp(0.0, 1.0),
+            iris,
+            iris_radius,
+            pupil_radius: iris_radius * state.pupil_size.clamp(0.0, 1.0),
+            glints,
+        }
+    }
+
+    /// Returns how much of the pixel lies between the eyelids
+    fn opening(&self, x: f32, y: f32) -> f32 {
+        let dx = (x - self.center.0) / self.half_width;
+        let lid = self.lid_height * (1.0 - dx * dx);
+        coverage(lid, (y - self.center.1).abs())
+    }
+
+    fn shade(&self, x: f32, y: f32) -> f32 {
+        let opening = self.opening(x, y);
+        if opening <= 0.0 {
+            return SKIN;
+        }
+
+        let (dx, dy) = (x - self.iris.0, y - self.iris.1);
+        let distance = dx.hypot(dy);
+        // radial fibers
+        let iris = IRIS + 12.0 * (14.0 * dy.atan2(dx)).sin() * distance / self.iris_radius;
+
+        let mut eye = mix(SCLERA, iris, coverage(self.iris_radius, distance));
+        eye = mix(eye, PUPIL, coverage(self.pupil_radius, distance));
+        for glint in &self.glints {
+            let distance = (x - glint.0).hypot(y - glint.1);
+            eye = mix(eye, GLINT, coverage(GLINT_RADIUS, distance));
+        }
+
+        mix(SKIN, eye, opening)
+    }
+}
+
+/// Anti-aliased coverage of a pixel at the given distance by a shape
+/// extending to the given radius
+fn coverage(radius: f32, distance: f32) -> f32 {
+    (radius - distance + 0.5).clamp(0.0, 1.0)
+}
+
+fn mix(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// xorshift64*, good enough for sensor noise and reproducible across
+/// platforms
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        Rng(seed | 1)
+    }
+
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// Renders procedural IR eye images, e.g. `synthetic://?fps=120&noise=8`
+/// - Frames are rendered as fast as they are requested, the pace is set by
+///   the target frame rate of [`crate::Camera`]
+/// - Connecting starts over at the first frame
+#[derive(Debug)]
+pub struct SyntheticEyeCamera {
+    eye: SyntheticEye,
+    index: Option<u64>,
+}
+
+impl SyntheticEyeCamera {
+    pub fn with_eye(mut self, eye: SyntheticEye) -> Self {
+        self.eye = eye;
+        self
+    }
+
+    /// Returns the eye being rendered, e.g. to look up the ground truth of
+    /// delivered frames
+    pub fn eye(&self) -> &SyntheticEye {
+        &self.eye
+    }
+}
+
+impl CameraHandler for SyntheticEyeCamera {
+    fn init() -> Self
+    where
+        Self: Sized,
+    {
+        Self {
+            eye: SyntheticEye::default(),
+            index: None,
+        }
+    }
+
+    fn get_frame(&mut self) -> Result<Frame, CameraState> {
+        let Some(index) = self.index.as_mut() else {
+            return Err(CameraState::Disconnected);
+        };
+
+        let frame = self.eye.render(*index);
+        *index += 1;
+        Ok(frame)
+    }
+
+    fn connect(&mut self, _source: String) -> Result<(), CameraState> {
+        if self.eye.width == 0 || self.eye.height == 0 {
+            return Err(CameraState::InvalidSource);
+        }
+
+        debug!(
+            "rendering synthetic {}x{} eye at {} fps",
+            self.eye.width, self.eye.height, self.eye.frame_rate
+        );
+        self.index = Some(0);
+        Ok(())
+    }
+
+    fn disconnect(&mut self) {
+        self.index = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Camera;
+
+    /// Finds the pupil as the centroid of all dark pixels
+    fn pupil_centroid(frame: &Frame) -> Option<(f32, f32)> {
+        let (mut sum_x, mut sum_y, mut count) = (0.0, 0.0, 0.0);
+        for (i, value) in frame.data.iter().enumerate() {
+            if *value < 40 {
+                sum_x += (i as u32 % frame.width) as f32 + 0.5;
+                sum_y += (i as u32 / frame.width) as f32 + 0.5;
+                count += 1.0;
+            }
+        }
+        (count > 0.0).then(|| (sum_x / count, sum_y / count))
+    }
+
+    #[test]
+    fn test_ground_truth() {
+        let eye = SyntheticEye::default().with_frame_rate(30);
+
+        for index in [0, 10, 25, 40, 77] {
+            let frame = eye.render(index);
+            assert_eq!(frame.data.len(), 240 * 240);
+
+            let truth = eye.ground_truth(index);
+            let (x, y) = pupil_centroid(&frame).expect("pupil should be visible");
+            assert!(
+                (x - truth.pupil_x).abs() < 1.0 && (y - truth.pupil_y).abs() < 1.0,
+                "frame {index}: found ({x}, {y}), expected {truth:?}"
+            );
+        }
+
+        // a quarter circle after a second, looking down
+        let truth = eye.ground_truth(30);
+        assert!((truth.pupil_x - 120.0).abs() < 0.01);
+        assert!(truth.pupil_y > 130.0);
+    }
+
+    #[test]
+    fn test_blinks_and_noise() {
+        let eye = SyntheticEye::default()
+            .with_script(GazeScript::Fixed(EyeState::default()))
+            .with_blinks(Duration::from_secs(2), Duration::from_millis(300));
+
+        // fully closed halfway through the blink
+        let closed = eye.ground_truth(9);
+        assert!(closed.state.openness < 0.01);
+        assert!(!closed.pupil_visible);
+        assert_eq!(pupil_centroid(&eye.render(9)), None);
+        assert!(eye.ground_truth(30).pupil_visible);
+
+        let noisy = eye
+            .clone()
+            .with_noise(10)
+            .with_motion_blur(Duration::from_millis(8));
+        assert_eq!(noisy.render(30).data, noisy.render(30).data);
+        assert_ne!(noisy.render(30).data, eye.render(30).data);
+        assert_ne!(
+            noisy.render(30).data,
+            noisy.clone().with_seed(7).render(30).data
+        );
+    }
+
+    #[test]
+    fn test_keyframes() {
+        let right = EyeState {
+            gaze_x: 1.0,
+            ..EyeState::default()
+        };
+        let script = GazeScript::Keyframes(vec![
+            (Duration::from_secs(1), EyeState::default()),
+            (Duration::from_secs(2), right),
+        ]);
+
+        assert_eq!(script.state_at(Duration::ZERO), EyeState::default());
+        assert_eq!(script.state_at(Duration::from_millis(1500)).gaze_x, 0.5);
+        assert_eq!(script.state_at(Duration::from_secs(5)), right);
+    }
+
+    #[test]
+    fn test_camera() {
+        let eye = SyntheticEye::default().with_size(160, 120).with_noise(4);
+        let handler = SyntheticEyeCamera::init().with_eye(eye.clone());
+        let mut camera = Camera::from_camera_handler(Box::new(handler), 500);
+        camera
+            .connect("synthetic".into())
+            .expect("synthetic camera should connect");
+
+        for _ in 0..5 {
+            let frame = camera
+                .get_frame_timeout(Duration::from_secs(1))
+                .expect("frames should be rendered");
+            assert_eq!((frame.width, frame.height), (160, 120));
+            assert_eq!(frame.data, eye.render(frame.sequence).data);
+        }
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+
+        let mut camera = Camera::open("synthetic://?width=64&height=48&blink=2000", 500)
+            .expect("synthetic source should open");
+        let frame = camera.get_frame_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!((frame.width, frame.height), (64, 48));
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+
+        assert_eq!(
+            Camera::open("synthetic://?width=0", 30).err(),
+            Some(CameraState::InvalidSource)
+        );
+    }
+}
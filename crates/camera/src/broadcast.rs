@@ -0,0 +1,254 @@
+use std::{
+    sync::{Arc, Mutex, MutexGuard, Weak},
+    time::Duration,
+};
+
+use crate::{BackpressurePolicy, CameraState, DroppedFrames, Frame, queue::FrameQueue};
+
+type SharedFrames = FrameQueue<Arc<Frame>>;
+
+#[derive(Debug)]
+struct Subscribers {
+    queues: Vec<Weak<SharedFrames>>,
+    closed: bool,
+}
+
+/// Fans out frames to all subscriptions, each with its own queue
+/// - Frames are shared, subscribers never receive a copy
+/// - Subscriptions that were dropped are pruned on the next frame
+#[derive(Debug)]
+pub(crate) struct FrameBroadcast {
+    subscribers: Mutex<Subscribers>,
+}
+
+impl Default for FrameBroadcast {
+    fn default() -> Self {
+        Self {
+            subscribers: Mutex::new(Subscribers {
+                queues: Vec::new(),
+                // opened once the camera connects
+                closed: true,
+            }),
+        }
+    }
+}
+
+impl FrameBroadcast {
+    fn lock(&self) -> MutexGuard<'_, Subscribers> {
+        self.subscribers.lock().expect("subscribers lock poisoned")
+    }
+
+    pub fn subscribe(&self, capacity: usize, policy: BackpressurePolicy) -> FrameSubscription {
+        let queue = Arc::new(FrameQueue::new(capacity, policy));
+
+        let mut subscribers = self.lock();
+        if subscribers.closed {
+            queue.close();
+        }
+        subscribers.queues.push(Arc::downgrade(&queue));
+
+        FrameSubscription { queue }
+    }
+
+    /// Queues the frame for every subscriber according to its policy
+    /// - Every subscriber with room receives the frame before waiting for full
+    ///   subscribers with [`BackpressurePolicy::Block`]
+    pub fn send(&self, frame: &Arc<Frame>) {
+        // pushing may block, the lock is released so closing can wake us up
+        let full: Vec<_> = self
+            .live_queues()
+            .into_iter()
+            .filter(|queue| queue.try_push(frame.clone()).is_err())
+            .collect();
+        for queue in full {
+            let _ = queue.push(frame.clone());
+        }
+    }
+
+    /// Closes all subscriptions, waking up blocked subscribers and the
+    /// capture thread
+    pub fn close(&self) {
+        let mut subscribers = self.lock();
+        subscribers.closed = true;
+        subscribers
+            .queues
+            .iter()
+            .filter_map(Weak::upgrade)
+            .for_each(|queue| queue.close());
+    }
+
+    /// Closes all subscriptions once they took the frames queued so far
+    pub fn finish(&self) {
+        let mut subscribers = self.lock();
+        subscribers.closed = true;
+        subscribers
+            .queues
+            .iter()
+            .filter_map(Weak::upgrade)
+            .for_each(|queue| queue.finish());
+    }
+
+    pub fn reopen(&self) {
+        let mut subscribers = self.lock();
+        subscribers.closed = false;
+        subscribers
+            .queues
+            .iter()
+            .filter_map(Weak::upgrade)
+            .for_each(|queue| queue.reopen());
+    }
+
+    fn live_queues(&self) -> Vec<Arc<SharedFrames>> {
+        let mut subscribers = self.lock();
+        let mut queues = Vec::with_capacity(subscribers.queues.len());
+        subscribers.queues.retain(|queue| match queue.upgrade() {
+            Some(queue) => {
+                queues.push(queue);
+                true
+            }
+            None => false,
+        });
+
+        queues
+    }
+}
+
+/// Receives the frames of a [`crate::Camera`] independent of other consumers,
+/// see [`crate::Camera::subscribe`]
+/// - Frames are shared with all other subscribers
+/// - Survives reconnects, but returns [`CameraState::Disconnected`] while the
+///   camera is not connected
+/// - Dropping the subscription unsubscribes
+#[derive(Debug)]
+pub struct FrameSubscription {
+    queue: Arc<SharedFrames>,
+}
+
+impl FrameSubscription {
+    /// Retrieves the next frame
+    /// - Blocks until a frame is available
+    pub fn get_frame(&self) -> Result<Arc<Frame>, CameraState> {
+        self.queue.pop().ok_or(CameraState::Disconnected)
+    }
+
+    /// Retrieves the next frame without blocking
+    /// - Returns `Ok(None)` if no frame is queued right now
+    pub fn try_get_frame(&self) -> Result<Option<Arc<Frame>>, CameraState> {
+        self.queue.try_pop().map_err(|_| CameraState::Disconnected)
+    }
+
+    /// Retrieves the next frame, waiting at most `timeout`
+    /// - Returns [`CameraState::Timeout`] if no frame arrived in time
+    pub fn get_frame_timeout(&self, timeout: Duration) -> Result<Arc<Frame>, CameraState> {
+        match self.queue.pop_timeout(timeout) {
+            Ok(Some(frame)) => Ok(frame),
+            Ok(None) => Err(CameraState::Timeout),
+            Err(_) => Err(CameraState::Disconnected),
+        }
+    }
+
+    /// Drains all queued frames and returns only the most recent one
+    /// - Returns `Ok(None)` if no frame is queued right now
+    pub fn latest_frame(&self) -> Result<Option<Arc<Frame>>, CameraState> {
+        self.queue
+            .drain_latest()
+            .map_err(|_| CameraState::Disconnected)
+    }
+
+    pub fn backpressure_policy(&self) -> BackpressurePolicy {
+        self.queue.policy()
+    }
+
+    /// Set what happens once this subscriber falls behind
+    pub fn set_backpressure_policy(&self, policy: BackpressurePolicy) {
+        self.queue.set_policy(policy);
+    }
+
+    /// Returns the amount of frames this subscriber missed, by policy
+    pub fn dropped_frames(&self) -> DroppedFrames {
+        self.queue.dropped()
+    }
+}
+
+impl Drop for FrameSubscription {
+    fn drop(&mut self) {
+        // releases the capture thread if it is blocked on this subscriber
+        self.queue.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PixelFormat;
+
+    fn frame(value: u8) -> Arc<Frame> {
+        Arc::new(Frame::packed(vec![value], 1, 1, PixelFormat::Gray8).unwrap())
+    }
+
+    #[test]
+    fn test_fan_out() {
+        let broadcast = FrameBroadcast::default();
+        let early = broadcast.subscribe(4, BackpressurePolicy::DropOldest);
+        assert_eq!(early.try_get_frame().err(), Some(CameraState::Disconnected));
+
+        broadcast.reopen();
+        let latest = broadcast.subscribe(4, BackpressurePolicy::Latest);
+        let dropped = broadcast.subscribe(4, BackpressurePolicy::Block);
+        drop(dropped);
+
+        let frames: Vec<_> = (0..3).map(frame).collect();
+        frames.iter().for_each(|frame| broadcast.send(frame));
+        assert_eq!(broadcast.lock().queues.len(), 2);
+
+        let received = early.get_frame().unwrap();
+        assert!(Arc::ptr_eq(&received, &frames[0]));
+        assert!(Arc::ptr_eq(&latest.get_frame().unwrap(), &frames[2]));
+        assert_eq!(latest.dropped_frames().latest, 2);
+        assert_eq!(early.dropped_frames().total(), 0);
+
+        broadcast.close();
+        assert_eq!(early.get_frame().err(), Some(CameraState::Disconnected));
+    }
+
+    #[test]
+    fn test_block_does_not_hold_back_others() {
+        let broadcast = Arc::new(FrameBroadcast::default());
+        broadcast.reopen();
+        let blocking = broadcast.subscribe(1, BackpressurePolicy::Block);
+        let preview = broadcast.subscribe(1, BackpressurePolicy::Latest);
+        broadcast.send(&frame(0));
+        assert_eq!(preview.get_frame().unwrap().data, [0]);
+
+        // the blocking subscriber is full, the preview still gets the frame
+        let sender = {
+            let broadcast = broadcast.clone();
+            std::thread::spawn(move || broadcast.send(&frame(1)))
+        };
+        let received = preview.get_frame_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(received.data, [1]);
+        assert!(!sender.is_finished());
+
+        assert_eq!(blocking.get_frame().unwrap().data, [0]);
+        sender.join().expect("sender should be released");
+        assert_eq!(blocking.get_frame().unwrap().data, [1]);
+    }
+
+    #[test]
+    fn test_drop_releases_sender() {
+        let broadcast = Arc::new(FrameBroadcast::default());
+        broadcast.reopen();
+        let subscription = broadcast.subscribe(1, BackpressurePolicy::Block);
+        broadcast.send(&frame(0));
+
+        let sender = {
+            let broadcast = broadcast.clone();
+            std::thread::spawn(move || broadcast.send(&frame(1)))
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!sender.is_finished());
+
+        drop(subscription);
+        sender.join().expect("sender should be released");
+    }
+}
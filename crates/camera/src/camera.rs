@@ -1,33 +1,201 @@
 use std::{
     fmt::Debug,
-    sync::{Arc, atomic, mpsc},
+    sync::{Arc, Mutex, Weak, atomic, mpsc},
+    time::{Duration, Instant},
 };
 
-use log::{debug, error, trace};
+use log::{debug, error, info, trace, warn};
 use replace_with::replace_with_or_abort_and_return;
 
-use crate::{CameraHandler, CameraHandlers, CameraState};
+use crate::{
+    BackendRegistry, BackpressurePolicy, CameraEvent, CameraHandler, CameraSource, CameraState,
+    CorruptFramePolicy, DroppedFrames, Frame, JpegValidation, PixelFormat, ReconnectPolicy,
+    Recorder, RecordingSummary,
+    broadcast::{FrameBroadcast, FrameSubscription},
+    events::EventBus,
+    queue::FrameQueue,
+    recorder::{RECORDER_BUFFER, RecorderThread},
+};
 
-// number of frames that will be kept in the channel
+// number of frames that will be kept in the queue
 const BUFFERED_FRAMES: usize = 30;
+// consumers may only use subscriptions, the queue must not stall the camera
+const BUFFERED_FRAMES_POLICY: BackpressurePolicy = BackpressurePolicy::DropOldest;
+// consecutive capture errors after which the handler thread starts backing off
+const ERROR_BACKOFF_THRESHOLD: u32 = 3;
+const ERROR_BACKOFF_BASE: Duration = Duration::from_millis(10);
+const ERROR_BACKOFF_MAX: Duration = Duration::from_secs(1);
+// how often a suspended handler thread checks whether it should stop
+const SUSPENDED_POLL: Duration = Duration::from_millis(10);
 
 type BoxedHandler = Box<dyn CameraHandler>;
+// shared with the subscribers, see `Camera::subscribe`
+type Frames = Arc<FrameQueue<Arc<Frame>>>;
 
 #[derive(Debug)]
 pub struct Atomics {
     should_stop: atomic::AtomicBool,
     frame_rate: atomic::AtomicU16,
     target_frame_rate: atomic::AtomicU16,
+    state: Mutex<CameraState>,
+    reconnect_policy: Mutex<Option<ReconnectPolicy>>,
+    source: Mutex<Option<String>>,
+    events: EventBus<CameraEvent>,
+    subscribers: FrameBroadcast,
+    jpeg_validation: Mutex<JpegValidation>,
+    corrupt_frame_policy: Mutex<CorruptFramePolicy>,
+    rejected_frames: atomic::AtomicU64,
+    // outlives the handler threads, see `Frame::sequence`
+    sequence: atomic::AtomicU64,
+    recorder: Mutex<Option<RecorderThread>>,
+    // name of the backend in the registry, if opened through one
+    backend: Mutex<Option<String>>,
+    // whether a handler thread is running and takes control requests
+    running: atomic::AtomicBool,
+    requests: mpsc::Sender<ControlRequest>,
+    // only ever locked by the handler thread
+    pending_requests: Mutex<mpsc::Receiver<ControlRequest>>,
 }
 
 impl Atomics {
     fn new(target_frame_rate: u16) -> Self {
+        let (requests, pending_requests) = mpsc::channel();
         Self {
             should_stop: atomic::AtomicBool::new(false),
             frame_rate: atomic::AtomicU16::new(0),
             target_frame_rate: atomic::AtomicU16::new(target_frame_rate),
+            state: Mutex::new(CameraState::Disconnected),
+            reconnect_policy: Mutex::new(None),
+            source: Mutex::new(None),
+            events: EventBus::default(),
+            subscribers: FrameBroadcast::default(),
+            jpeg_validation: Mutex::new(JpegValidation::default()),
+            corrupt_frame_policy: Mutex::new(CorruptFramePolicy::default()),
+            rejected_frames: atomic::AtomicU64::new(0),
+            sequence: atomic::AtomicU64::new(0),
+            recorder: Mutex::new(None),
+            backend: Mutex::new(None),
+            running: atomic::AtomicBool::new(false),
+            requests,
+            pending_requests: Mutex::new(pending_requests),
+        }
+    }
+
+    /// Takes the next control request, waiting at most `timeout` for one
+    fn next_request(&self, timeout: Duration) -> Option<ControlRequest> {
+        self.pending_requests
+            .lock()
+            .expect("requests lock poisoned")
+            .recv_timeout(timeout)
+            .ok()
+    }
+
+    fn source(&self) -> Option<String> {
+        self.source.lock().expect("source lock poisoned").clone()
+    }
+
+    fn set_source(&self, source: String) {
+        *self.source.lock().expect("source lock poisoned") = Some(source);
+    }
+
+    fn reconnect_policy(&self) -> Option<ReconnectPolicy> {
+        self.reconnect_policy
+            .lock()
+            .expect("reconnect policy lock poisoned")
+            .clone()
+    }
+
+    fn state(&self) -> CameraState {
+        self.state.lock().expect("state lock poisoned").clone()
+    }
+
+    /// Stores the new state and notifies subscribers
+    /// - Returns true if it differs from the previous one
+    fn set_state(&self, state: CameraState) -> bool {
+        let mut current = self.state.lock().expect("state lock poisoned");
+        if *current == state {
+            return false;
+        }
+
+        debug!("camera state changed: {:?} -> {state:?}", *current);
+        let previous = std::mem::replace(&mut *current, state.clone());
+
+        // emitted while holding the lock, keeping events in transition order
+        self.events.emit(CameraEvent {
+            previous,
+            state,
+            source: self.source.lock().expect("source lock poisoned").clone(),
+            timestamp: Instant::now(),
+        });
+        true
+    }
+}
+
+/// Requests carried out by the [`handler_recv`] thread, see [`CameraControl`]
+#[derive(Debug)]
+enum ControlRequest {
+    Disconnect,
+    Switch(String),
+}
+
+/// Controls the source of a [`Camera`] without locking it, see
+/// [`Camera::control`]
+/// - Requests are carried out by the capture thread, consumers blocked in
+///   [`Camera::get_frame`] are woken up instead of being waited for
+/// - Does not keep the camera alive, requests fail once it was dropped
+#[derive(Debug, Clone)]
+pub struct CameraControl {
+    atomics: Weak<Atomics>,
+}
+
+impl CameraControl {
+    /// Returns the current state of the camera, see [`Camera::status`]
+    pub fn status(&self) -> CameraState {
+        match self.atomics.upgrade() {
+            Some(atomics) if atomics.running.load(atomic::Ordering::Relaxed) => atomics.state(),
+            _ => CameraState::Disconnected,
         }
     }
+
+    /// Returns the source the camera is connected to, or was last connected to
+    pub fn source(&self) -> Option<String> {
+        self.atomics.upgrade()?.source()
+    }
+
+    /// Disconnects the backend, e.g. because its device vanished
+    /// - Queued frames are dropped and consumers receive
+    ///   [`CameraState::Disconnected`]
+    /// - The capture thread is kept around, reconnect with
+    ///   [`CameraControl::request_switch`] or [`Camera::switch_source`]
+    /// - Returns [`CameraState::Disconnected`] if the camera is not connected
+    pub fn request_disconnect(&self) -> Result<(), CameraState> {
+        self.request(ControlRequest::Disconnect)
+    }
+
+    /// Connects the backend to another source, dropping queued frames of the
+    /// previous one
+    /// - The camera stays disconnected if the new source fails to connect
+    /// - Returns [`CameraState::Disconnected`] if the camera is not connected
+    pub fn request_switch(&self, source: String) -> Result<(), CameraState> {
+        self.request(ControlRequest::Switch(source))
+    }
+
+    /// Whether the camera was dropped
+    pub(crate) fn is_dropped(&self) -> bool {
+        self.atomics.strong_count() == 0
+    }
+
+    fn request(&self, request: ControlRequest) -> Result<(), CameraState> {
+        let atomics = self.atomics.upgrade().ok_or(CameraState::Disconnected)?;
+        if !atomics.running.load(atomic::Ordering::Relaxed) {
+            return Err(CameraState::Disconnected);
+        }
+
+        atomics
+            .requests
+            .send(request)
+            .map_err(|_| CameraState::Disconnected)
+    }
 }
 
 #[derive(Debug)]
@@ -35,7 +203,6 @@ enum InternalState {
     Waiting(BoxedHandler),
     Connected {
         thread: std::thread::JoinHandle<BoxedHandler>,
-        frame_rx: mpsc::Receiver<Vec<u8>>,
     },
 }
 
@@ -43,44 +210,162 @@ enum InternalState {
 pub struct Camera {
     state: InternalState,
     atomics: Arc<Atomics>,
+    frames: Frames,
 }
 
 impl Camera {
-    pub fn new(handler: CameraHandlers, frame_rate: u16) -> Camera {
+    #[deprecated(
+        note = "use `Camera::open` or `Camera::open_with`, backends are picked through the `BackendRegistry`"
+    )]
+    #[allow(deprecated)]
+    pub fn new(handler: crate::CameraHandlers, frame_rate: u16) -> Camera {
         use crate::backends::*;
 
-        #[allow(unreachable_patterns)]
         let backend: BoxedHandler = match handler {
             CameraHandlers::NoOp => Box::new(NoOpCamera::init()),
             CameraHandlers::OpenCV => Box::new(OpenCVCamera::init()),
             CameraHandlers::OpenIris => Box::new(OpenIrisCamera::init()),
-            _ => {
-                panic!("Unsupported camera handler");
-            }
         };
 
         Self::from_camera_handler(backend, frame_rate)
     }
 
+    /// Creates a camera for the given source URI and connects to it
+    /// - The backend is picked from the built-in backends based on the scheme,
+    ///   see [`CameraSource`]
+    /// - Returns [`CameraState::InvalidSource`] if the URI is malformed or no
+    ///   backend supports its scheme
+    pub fn open(uri: &str, target_frame_rate: u16) -> Result<Camera, CameraState> {
+        Self::open_with(&BackendRegistry::default(), uri, target_frame_rate)
+    }
+
+    /// Creates a camera for the given source URI using the backends of the
+    /// given registry and connects to it
+    pub fn open_with(
+        registry: &BackendRegistry,
+        uri: &str,
+        target_frame_rate: u16,
+    ) -> Result<Camera, CameraState> {
+        let source = CameraSource::parse(uri)?;
+        let handler = registry.create(&source)?;
+
+        let mut camera = Self::from_camera_handler(handler, target_frame_rate);
+        *camera
+            .atomics
+            .backend
+            .lock()
+            .expect("backend lock poisoned") =
+            registry.backend_name(source.scheme()).map(str::to_string);
+        camera.connect(source.location())?;
+
+        Ok(camera)
+    }
+
     pub fn from_camera_handler(handler: BoxedHandler, target_frame_rate: u16) -> Camera {
         Self {
             state: InternalState::Waiting(handler),
             atomics: Arc::new(Atomics::new(target_frame_rate)),
+            frames: Arc::new(FrameQueue::new(BUFFERED_FRAMES, BUFFERED_FRAMES_POLICY)),
+        }
+    }
+
+    /// Retrieves the next frame from the camera
+    /// - Blocks until a frame is available
+    /// - Returns an error if the camera is not connected
+    pub fn get_frame(&self) -> Result<Frame, CameraState> {
+        self.ensure_connected()?;
+        self.frames
+            .pop()
+            .map(Arc::unwrap_or_clone)
+            .ok_or(CameraState::Disconnected)
+    }
+
+    /// Retrieves the next frame from the camera without blocking
+    /// - Returns `Ok(None)` if no frame is queued right now
+    /// - Returns an error if the camera is not connected
+    pub fn try_get_frame(&self) -> Result<Option<Frame>, CameraState> {
+        self.ensure_connected()?;
+        self.frames
+            .try_pop()
+            .map(|frame| frame.map(Arc::unwrap_or_clone))
+            .map_err(|_| CameraState::Disconnected)
+    }
+
+    /// Retrieves the next frame from the camera, waiting at most `timeout`
+    /// - Returns [`CameraState::Timeout`] if no frame arrived in time
+    /// - Returns an error if the camera is not connected
+    pub fn get_frame_timeout(&self, timeout: Duration) -> Result<Frame, CameraState> {
+        self.ensure_connected()?;
+        match self.frames.pop_timeout(timeout) {
+            Ok(Some(frame)) => Ok(Arc::unwrap_or_clone(frame)),
+            Ok(None) => Err(CameraState::Timeout),
+            Err(_) => Err(CameraState::Disconnected),
         }
     }
 
-    /// Retrieves the current most recent frame from the camera
+    /// Drains all queued frames and returns only the most recent one
+    /// - Older frames are discarded
+    /// - Returns `Ok(None)` if no frame is queued right now
     /// - Returns an error if the camera is not connected
-    pub fn get_frame(&self) -> Result<Vec<u8>, CameraState> {
+    pub fn latest_frame(&self) -> Result<Option<Frame>, CameraState> {
+        self.ensure_connected()?;
+        self.frames
+            .drain_latest()
+            .map(|frame| frame.map(Arc::unwrap_or_clone))
+            .map_err(|_| CameraState::Disconnected)
+    }
+
+    /// Returns the current state of the camera
+    /// - Reflects the outcome of the most recent capture attempt while
+    ///   connected, e.g. [`CameraState::ReadFailed`] or
+    ///   [`CameraState::Timeout`]
+    pub fn status(&self) -> CameraState {
+        match &self.state {
+            InternalState::Waiting(..) => CameraState::Disconnected,
+            InternalState::Connected { .. } => self.atomics.state(),
+        }
+    }
+
+    /// Returns the source the camera is connected to, or was last connected to
+    pub fn source(&self) -> Option<String> {
+        self.atomics.source()
+    }
+
+    /// Subscribes to the frames of the camera with a queue of its own
+    /// - Every subscriber receives every frame, independent of other
+    ///   subscribers and of [`Camera::get_frame`]
+    /// - The policy only applies to this subscriber, use
+    ///   [`BackpressurePolicy::Latest`] for a single slot holding the newest
+    ///   frame
+    /// - A full subscriber with [`BackpressurePolicy::Block`] throttles the
+    ///   camera to its pace, the other subscribers still receive every frame
+    ///   before the camera waits for it
+    /// - Frames are shared, [`Camera::get_frame`] only copies a frame if a
+    ///   subscriber still holds it
+    pub fn subscribe(&self, capacity: usize, policy: BackpressurePolicy) -> FrameSubscription {
+        self.atomics.subscribers.subscribe(capacity, policy)
+    }
+
+    /// Returns a handle controlling the source of the camera from another
+    /// thread, even while a consumer holds on to the camera
+    pub fn control(&self) -> CameraControl {
+        CameraControl {
+            atomics: Arc::downgrade(&self.atomics),
+        }
+    }
+
+    /// Subscribes to state transitions of the camera and its handler thread
+    /// - Every transition is delivered, e.g. `Connecting`, `Connected`,
+    ///   `ReadFailed` or `Disconnected`
+    /// - Dropping the receiver unsubscribes
+    pub fn subscribe_events(&self) -> mpsc::Receiver<CameraEvent> {
+        self.atomics.events.subscribe()
+    }
+
+    fn ensure_connected(&self) -> Result<(), CameraState> {
         match &self.state {
             InternalState::Waiting(..) => Err(CameraState::Disconnected),
-            InternalState::Connected {
-                thread: _,
-                frame_rx,
-            } => match frame_rx.recv() {
-                Ok(mut frame) => Ok(std::mem::take(&mut frame)),
-                Err(e) => Err(CameraState::Error(e.to_string())),
-            },
+            InternalState::Connected { .. } => Ok(()),
         }
     }
 
@@ -96,14 +381,62 @@ impl Camera {
                 // the handle later gets reclaimed in [`Camera::disconnect`], see return value
                 // of the thread binding in current scope.
 
-                if let Err(e) = handler.connect(source) {
+                if let Err(e) = connect_handler(&mut handler, &source, &self.atomics) {
+                    self.atomics.set_state(CameraState::Disconnected);
                     return (Err(e), InternalState::Waiting(handler));
                 };
 
-                let (frame_tx, frame_rx) = mpsc::sync_channel(BUFFERED_FRAMES);
-                let thread = handler_recv(handler, frame_tx, self.atomics.clone());
+                let thread = spawn_handler(handler, source, &self.frames, &self.atomics);
+                (Ok(()), InternalState::Connected { thread })
+            }
+        })
+    }
+
+    /// Switches a connected camera over to another source
+    /// - Queued frames of the previous source are dropped, but the frame queue,
+    ///   its policy and all event subscribers are kept
+    /// - Rolls back to the previous source if the new one fails to connect
+    /// - Behaves like [`Camera::connect`] if the camera is not connected
+    pub fn switch_source(&mut self, source: String) -> Result<(), CameraState> {
+        if let InternalState::Waiting(..) = self.state {
+            return self.connect(source);
+        }
+
+        replace_with_or_abort_and_return(&mut self.state, |state| {
+            let InternalState::Connected { thread } = state else {
+                unreachable!("checked above")
+            };
+
+            let mut handler = reclaim_handler(thread, &self.frames, &self.atomics);
+            let previous = self.atomics.source();
+            handler.disconnect();
+
+            let error = match connect_handler(&mut handler, &source, &self.atomics) {
+                Ok(()) => {
+                    info!("switched source to {source}");
+                    let thread = spawn_handler(handler, source, &self.frames, &self.atomics);
+                    return (Ok(()), InternalState::Connected { thread });
+                }
+                Err(e) => e,
+            };
 
-                (Ok(()), InternalState::Connected { thread, frame_rx })
+            warn!("failed to switch source to {source}: {error:?}");
+            let Some(previous) = previous else {
+                self.atomics.set_state(CameraState::Disconnected);
+                return (Err(error), InternalState::Waiting(handler));
+            };
+
+            match connect_handler(&mut handler, &previous, &self.atomics) {
+                Ok(()) => {
+                    info!("rolled back to previous source {previous}");
+                    let thread = spawn_handler(handler, previous, &self.frames, &self.atomics);
+                    (Err(error), InternalState::Connected { thread })
+                }
+                Err(e) => {
+                    error!("failed to roll back to previous source {previous}: {e:?}");
+                    self.atomics.set_state(CameraState::Disconnected);
+                    (Err(error), InternalState::Waiting(handler))
+                }
             }
         })
     }
@@ -111,19 +444,11 @@ impl Camera {
     /// Disconnects the camera
     pub fn disconnect(&mut self) -> Result<(), CameraState> {
         replace_with_or_abort_and_return(&mut self.state, |state| match state {
-            InternalState::Connected { thread, frame_rx } => {
-                self.atomics
-                    .should_stop
-                    .store(true, atomic::Ordering::Relaxed);
-
-                // purge frame_rx by consuming all remaining elements
-                let count = frame_rx.recv().iter().count();
-                trace!("purged {count} frames from receive queue");
-
-                // reclaim our injected camera implementation handler from thread
-                let handler = thread.join().expect("receive thread has panicked");
-                trace!("reclaimed handler from thread");
+            InternalState::Connected { thread } => {
+                let mut handler = reclaim_handler(thread, &self.frames, &self.atomics);
+                handler.disconnect();
 
+                self.atomics.set_state(CameraState::Disconnected);
                 (Ok(()), InternalState::Waiting(handler))
             }
             state => (
@@ -153,34 +478,343 @@ impl Camera {
             .target_frame_rate
             .store(target_frame_rate, atomic::Ordering::Relaxed)
     }
+
+    /// Returns the policy used to reconnect a stalled backend
+    /// - `None` if the camera is not supervised
+    pub fn reconnect_policy(&self) -> Option<ReconnectPolicy> {
+        self.atomics.reconnect_policy()
+    }
+
+    /// Supervises the backend, reconnecting it to the last source once it
+    /// stalls or keeps failing
+    /// - `None` disables supervision, capture errors are then only reported
+    ///   via [`Camera::status`]
+    /// - Can be changed at any time, including while connected
+    pub fn set_reconnect_policy(&self, policy: Option<ReconnectPolicy>) {
+        *self
+            .atomics
+            .reconnect_policy
+            .lock()
+            .expect("reconnect policy lock poisoned") = policy;
+    }
+
+    /// Returns the policy applied once the frame queue is full
+    /// - [`BackpressurePolicy::DropOldest`] by default, the queue never stalls
+    ///   the camera when only subscriptions are used
+    pub fn backpressure_policy(&self) -> BackpressurePolicy {
+        self.frames.policy()
+    }
+
+    /// Set the policy applied once the frame queue is full
+    /// - Can be changed at any time, including while connected
+    pub fn set_backpressure_policy(&self, policy: BackpressurePolicy) {
+        self.frames.set_policy(policy)
+    }
+
+    /// Returns the amount of frames dropped by each backpressure policy since
+    /// the camera was created
+    pub fn dropped_frames(&self) -> DroppedFrames {
+        self.frames.dropped()
+    }
+
+    /// Returns how thoroughly JPEG frames are checked
+    pub fn jpeg_validation(&self) -> JpegValidation {
+        *self
+            .atomics
+            .jpeg_validation
+            .lock()
+            .expect("jpeg validation lock poisoned")
+    }
+
+    /// Set how thoroughly JPEG frames are checked before being queued
+    /// - Can be changed at any time, including while connected
+    pub fn set_jpeg_validation(&self, validation: JpegValidation) {
+        *self
+            .atomics
+            .jpeg_validation
+            .lock()
+            .expect("jpeg validation lock poisoned") = validation;
+    }
+
+    /// Returns what happens to frames failing validation
+    pub fn corrupt_frame_policy(&self) -> CorruptFramePolicy {
+        *self
+            .atomics
+            .corrupt_frame_policy
+            .lock()
+            .expect("corrupt frame policy lock poisoned")
+    }
+
+    /// Set what happens to frames failing validation
+    /// - Can be changed at any time, including while connected
+    pub fn set_corrupt_frame_policy(&self, policy: CorruptFramePolicy) {
+        *self
+            .atomics
+            .corrupt_frame_policy
+            .lock()
+            .expect("corrupt frame policy lock poisoned") = policy;
+    }
+
+    /// Returns the amount of frames that failed validation since the camera
+    /// was created, whether they were dropped or flagged
+    /// - A steadily rising count usually points at a bad cable or connector
+    pub fn rejected_frames(&self) -> u64 {
+        self.atomics.rejected_frames.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Starts writing every delivered frame to the recorder
+    /// - Frames are written on a thread of its own, if it falls behind the
+    ///   oldest frames are skipped instead of holding up the camera, see
+    ///   [`RecordingSummary::skipped`]
+    /// - Fills in the source and, for cameras created by [`Camera::open`], the
+    ///   backend unless the recorder already has them
+    /// - Returns an error if the camera is already recording
+    pub fn start_recording(&self, mut recorder: Recorder) -> Result<(), CameraState> {
+        let mut current = self
+            .atomics
+            .recorder
+            .lock()
+            .expect("recorder lock poisoned");
+        if current.is_some() {
+            return Err(CameraState::Error(
+                "Camera is already recording".to_string(),
+            ));
+        }
+
+        let metadata = recorder.metadata_mut();
+        if metadata.source.is_none() {
+            metadata.source = self.atomics.source();
+        }
+        if metadata.backend.is_none() {
+            metadata.backend = self
+                .atomics
+                .backend
+                .lock()
+                .expect("backend lock poisoned")
+                .clone();
+        }
+
+        let frames = self
+            .atomics
+            .subscribers
+            .subscribe(RECORDER_BUFFER, BackpressurePolicy::DropOldest);
+        *current = Some(RecorderThread::spawn(recorder, frames));
+        Ok(())
+    }
+
+    /// Stops recording and finishes the file
+    /// - Returns [`CameraState::Error`] if the camera was not recording or
+    ///   writing the file failed
+    pub fn stop_recording(&self) -> Result<RecordingSummary, CameraState> {
+        let recorder = self
+            .atomics
+            .recorder
+            .lock()
+            .expect("recorder lock poisoned")
+            .take();
+
+        match recorder {
+            Some(recorder) => recorder.finish(),
+            None => Err(CameraState::Error("Camera is not recording".to_string())),
+        }
+    }
+
+    /// Whether frames are being recorded
+    /// - Turns false once the size limit of the recorder was reached, the
+    ///   file is only finished by [`Camera::stop_recording`]
+    pub fn is_recording(&self) -> bool {
+        self.atomics
+            .recorder
+            .lock()
+            .expect("recorder lock poisoned")
+            .as_ref()
+            .is_some_and(RecorderThread::is_recording)
+    }
+}
+
+/// Connects the handler to the given source while reporting the transitions
+fn connect_handler(
+    handler: &mut BoxedHandler,
+    source: &str,
+    atomics: &Atomics,
+) -> Result<(), CameraState> {
+    atomics.set_source(source.to_string());
+    atomics.set_state(CameraState::Connecting);
+
+    match handler.connect(source.to_string()) {
+        Ok(()) => {
+            atomics.set_state(CameraState::Connected);
+            Ok(())
+        }
+        Err(e) => {
+            atomics.set_state(e.clone());
+            Err(e)
+        }
+    }
+}
+
+/// Hands a connected handler off to a fresh [`handler_recv`] thread
+fn spawn_handler(
+    handler: BoxedHandler,
+    source: String,
+    frames: &Frames,
+    atomics: &Arc<Atomics>,
+) -> std::thread::JoinHandle<BoxedHandler> {
+    atomics.should_stop.store(false, atomic::Ordering::Relaxed);
+    frames.reopen();
+    atomics.subscribers.reopen();
+
+    // requests sent while no thread was running are stale by now
+    while atomics.next_request(Duration::ZERO).is_some() {}
+    atomics.running.store(true, atomic::Ordering::Relaxed);
+
+    handler_recv(handler, source, frames.clone(), atomics.clone())
+}
+
+/// Stops the [`handler_recv`] thread and reclaims its handler
+fn reclaim_handler(
+    thread: std::thread::JoinHandle<BoxedHandler>,
+    frames: &Frames,
+    atomics: &Atomics,
+) -> BoxedHandler {
+    atomics.running.store(false, atomic::Ordering::Relaxed);
+    atomics.should_stop.store(true, atomic::Ordering::Relaxed);
+
+    // purges all remaining frames and unblocks the thread if it is waiting
+    // for room in the queue
+    frames.close();
+    atomics.subscribers.close();
+
+    // reclaim our injected camera implementation handler from thread
+    let handler = thread.join().expect("receive thread has panicked");
+    trace!("reclaimed handler from thread");
+
+    handler
 }
 
 pub fn handler_recv(
     mut handler: BoxedHandler,
-    frame_tx: mpsc::SyncSender<Vec<u8>>,
+    mut source: String,
+    frames: Frames,
     atomics: Arc<Atomics>,
 ) -> std::thread::JoinHandle<BoxedHandler> {
     std::thread::spawn(move || {
+        let mut errors = 0;
+        let mut last_frame = Instant::now();
+        // disconnected through a control request or by giving up, the thread
+        // only waits for requests
+        let mut suspended = false;
+
         loop {
             if atomics.should_stop.load(atomic::Ordering::Relaxed) {
                 break;
             }
 
+            let timeout = if suspended {
+                SUSPENDED_POLL
+            } else {
+                Duration::ZERO
+            };
+            match atomics.next_request(timeout) {
+                Some(ControlRequest::Disconnect) => {
+                    info!("disconnecting from {source} on request");
+                    suspend(&mut handler, &frames, &atomics);
+                    suspended = true;
+                    continue;
+                }
+                Some(ControlRequest::Switch(next)) => {
+                    suspend(&mut handler, &frames, &atomics);
+                    suspended = true;
+                    if let Err(e) = connect_handler(&mut handler, &next, &atomics) {
+                        warn!("failed to switch source to {next}: {e:?}");
+                        atomics.set_state(CameraState::Disconnected);
+                        continue;
+                    }
+
+                    info!("switched source to {next}");
+                    frames.reopen();
+                    atomics.subscribers.reopen();
+                    source = next;
+                    suspended = false;
+                    errors = 0;
+                    last_frame = Instant::now();
+                    continue;
+                }
+                None if suspended => continue,
+                None => {}
+            }
+
             let target_fps = atomics.target_frame_rate.load(atomic::Ordering::Relaxed) as u64;
             let frame_time = std::time::Instant::now();
 
-            let frame = match handler.get_frame() {
-                Ok(frame) => frame,
-                Err(_) => {
-                    // TODO: proper error handling
-                    // error!("failed to fetch frame: {e:?}");
+            let mut frame = match handler.get_frame() {
+                Ok(frame) => {
+                    if errors > 0 {
+                        info!("recovered after {errors} failed capture attempts");
+                        errors = 0;
+                    }
+                    atomics.set_state(CameraState::Connected);
+                    last_frame = Instant::now();
+
+                    frame
+                }
+                Err(CameraState::EndOfStream) => {
+                    // a clean stop, neither backing off nor reconnecting
+                    info!("{source} has no more frames");
+                    handler.disconnect();
+                    // set first, woken up consumers already see it
+                    atomics.set_state(CameraState::EndOfStream);
+                    // consumers still receive the queued frames
+                    frames.finish();
+                    atomics.subscribers.finish();
+                    suspended = true;
+                    continue;
+                }
+                Err(e) => {
+                    errors += 1;
+                    if atomics.set_state(e.clone()) {
+                        error!("failed to fetch frame: {e:?}");
+                    }
+
+                    let stalled = atomics
+                        .reconnect_policy()
+                        .filter(|policy| policy.is_stalled(errors, last_frame.elapsed()));
+                    if let Some(policy) = stalled {
+                        if !reconnect(&mut handler, &source, &policy, &atomics) {
+                            // wake up consumers, there won't be any more frames
+                            // unless the source is switched
+                            suspend(&mut handler, &frames, &atomics);
+                            suspended = true;
+                            continue;
+                        }
+
+                        errors = 0;
+                        last_frame = Instant::now();
+                        continue;
+                    }
+
+                    // back off exponentially instead of hammering a dead device
+                    if errors >= ERROR_BACKOFF_THRESHOLD {
+                        sleep_unless_stopped(&atomics, error_backoff(errors));
+                    }
                     continue;
                 }
             };
 
-            if let Err(e) = frame_tx.send(frame) {
-                // todo: abort thread here? this state should never be hit
-                error!("failed to send frame to internal channel: {e:?}");
+            if !validate_frame(&mut frame, &atomics) {
+                continue;
+            }
+
+            if !handler.keeps_sequence() {
+                frame.sequence = atomics.sequence.fetch_add(1, atomic::Ordering::Relaxed);
+            }
+
+            let frame = Arc::new(frame);
+            atomics.subscribers.send(&frame);
+
+            if frames.push(frame).is_err() {
+                trace!("frame queue closed, dropping frame");
+                continue;
             }
 
             // sleep for consistent FPS
@@ -201,12 +835,277 @@ pub fn handler_recv(
     })
 }
 
+/// Disconnects the backend while keeping the [`handler_recv`] thread around
+/// - Wakes up consumers, dropping queued frames of the previous source
+fn suspend(handler: &mut BoxedHandler, frames: &Frames, atomics: &Atomics) {
+    handler.disconnect();
+    frames.close();
+    atomics.subscribers.close();
+    atomics.set_state(CameraState::Disconnected);
+}
+
+/// Reconnects a stalled backend to its last source
+/// - Returns false if the policy gave up or the camera is being disconnected
+fn reconnect(
+    handler: &mut BoxedHandler,
+    source: &str,
+    policy: &ReconnectPolicy,
+    atomics: &Atomics,
+) -> bool {
+    warn!("camera stalled, reconnecting to {source}");
+    let mut failed_attempts = 0;
+
+    loop {
+        if atomics.should_stop.load(atomic::Ordering::Relaxed) {
+            return false;
+        }
+
+        atomics.set_state(CameraState::Connecting);
+        handler.disconnect();
+
+        match handler.connect(source.to_string()) {
+            Ok(()) => {
+                info!("reconnected to {source} after {failed_attempts} failed attempts");
+                atomics.set_state(CameraState::Connected);
+                return true;
+            }
+            Err(e) => {
+                failed_attempts += 1;
+                debug!("reconnect attempt {failed_attempts} failed: {e:?}");
+                atomics.set_state(e);
+
+                if !policy.should_retry(failed_attempts) {
+                    error!("giving up on {source} after {failed_attempts} reconnect attempts");
+                    atomics.set_state(CameraState::Disconnected);
+                    return false;
+                }
+                sleep_unless_stopped(atomics, policy.backoff(failed_attempts));
+            }
+        }
+    }
+}
+
+/// Checks JPEG frames according to the configured validation
+/// - Returns false if the frame is corrupt and should be dropped
+fn validate_frame(frame: &mut Frame, atomics: &Atomics) -> bool {
+    if frame.format != PixelFormat::Jpeg {
+        return true;
+    }
+
+    let validation = *atomics
+        .jpeg_validation
+        .lock()
+        .expect("jpeg validation lock poisoned");
+    let Err(e) = validation.validate(&frame.data) else {
+        return true;
+    };
+
+    let rejected = atomics
+        .rejected_frames
+        .fetch_add(1, atomic::Ordering::Relaxed)
+        + 1;
+    debug!("corrupt jpeg frame ({rejected} so far): {e:?}");
+
+    let policy = *atomics
+        .corrupt_frame_policy
+        .lock()
+        .expect("corrupt frame policy lock poisoned");
+    match policy {
+        CorruptFramePolicy::Drop => false,
+        CorruptFramePolicy::Flag => {
+            frame.corruption = Some(e);
+            true
+        }
+    }
+}
+
+/// Sleeps for the given duration, returning early once the camera is being
+/// disconnected
+fn sleep_unless_stopped(atomics: &Atomics, duration: Duration) {
+    const SLICE: Duration = Duration::from_millis(10);
+    let deadline = Instant::now() + duration;
+
+    while !atomics.should_stop.load(atomic::Ordering::Relaxed) {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        std::thread::sleep(remaining.min(SLICE));
+    }
+}
+
+fn error_backoff(errors: u32) -> Duration {
+    let exponent = (errors - ERROR_BACKOFF_THRESHOLD).min(16);
+    (ERROR_BACKOFF_BASE * 2u32.pow(exponent)).min(ERROR_BACKOFF_MAX)
+}
+
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
     use super::*;
+    use crate::NoOpCamera;
+
+    /// Backend whose every capture attempt fails with the given state
+    #[derive(Debug)]
+    struct FailingCamera {
+        error: CameraState,
+        attempts: Arc<AtomicUsize>,
+    }
+
+    impl CameraHandler for FailingCamera {
+        fn init() -> Self
+        where
+            Self: Sized,
+        {
+            Self {
+                error: CameraState::ReadFailed,
+                attempts: Arc::default(),
+            }
+        }
+
+        fn get_frame(&mut self) -> Result<Frame, CameraState> {
+            self.attempts.fetch_add(1, Ordering::Relaxed);
+            Err(self.error.clone())
+        }
+
+        fn connect(&mut self, _source: String) -> Result<(), CameraState> {
+            Ok(())
+        }
+
+        fn disconnect(&mut self) {}
+    }
+
+    /// Backend that drops out once after a few frames and refuses the
+    /// following reconnect attempts
+    #[derive(Debug)]
+    struct FlakyCamera {
+        frames_until_dropout: usize,
+        refused_connects: usize,
+        delivered: usize,
+        connects: Arc<AtomicUsize>,
+    }
+
+    impl CameraHandler for FlakyCamera {
+        fn init() -> Self
+        where
+            Self: Sized,
+        {
+            Self {
+                frames_until_dropout: 5,
+                refused_connects: 0,
+                delivered: 0,
+                connects: Arc::default(),
+            }
+        }
+
+        fn get_frame(&mut self) -> Result<Frame, CameraState> {
+            if self.delivered >= self.frames_until_dropout {
+                return Err(CameraState::Disconnected);
+            }
+
+            self.delivered += 1;
+            Frame::packed(vec![0], 1, 1, crate::PixelFormat::Gray8)
+        }
+
+        fn connect(&mut self, _source: String) -> Result<(), CameraState> {
+            // the very first connect is performed by the caller and succeeds
+            if self.connects.fetch_add(1, Ordering::Relaxed) == 0 {
+                return Ok(());
+            }
+            if self.refused_connects > 0 {
+                self.refused_connects -= 1;
+                return Err(CameraState::Timeout);
+            }
+
+            self.frames_until_dropout = usize::MAX;
+            Ok(())
+        }
+
+        fn disconnect(&mut self) {}
+    }
+
+    /// Backend refusing every source starting with `bad`
+    #[derive(Debug)]
+    struct PickyCamera {
+        source: Option<String>,
+    }
+
+    impl CameraHandler for PickyCamera {
+        fn init() -> Self
+        where
+            Self: Sized,
+        {
+            Self { source: None }
+        }
+
+        fn get_frame(&mut self) -> Result<Frame, CameraState> {
+            match self.source {
+                Some(_) => Frame::packed(vec![0], 1, 1, crate::PixelFormat::Gray8),
+                None => Err(CameraState::Disconnected),
+            }
+        }
+
+        fn connect(&mut self, source: String) -> Result<(), CameraState> {
+            if source.starts_with("bad") {
+                return Err(CameraState::InvalidSource);
+            }
+
+            self.source = Some(source);
+            Ok(())
+        }
+
+        fn disconnect(&mut self) {
+            self.source = None;
+        }
+    }
+
+    /// Backend alternating between intact and truncated JPEG frames
+    #[derive(Debug)]
+    struct CorruptingCamera {
+        delivered: usize,
+    }
+
+    impl CameraHandler for CorruptingCamera {
+        fn init() -> Self
+        where
+            Self: Sized,
+        {
+            Self { delivered: 0 }
+        }
+
+        fn get_frame(&mut self) -> Result<Frame, CameraState> {
+            const GRAY_8X8: &[u8] = include_bytes!("../tests/data/gray_8x8.jpg");
+
+            self.delivered += 1;
+            let len = match self.delivered % 2 {
+                0 => GRAY_8X8.len() / 2,
+                _ => GRAY_8X8.len(),
+            };
+            Ok(Frame::jpeg(GRAY_8X8[..len].to_vec()))
+        }
+
+        fn connect(&mut self, _source: String) -> Result<(), CameraState> {
+            Ok(())
+        }
+
+        fn disconnect(&mut self) {}
+    }
+
+    fn fast_reconnect_policy() -> ReconnectPolicy {
+        ReconnectPolicy {
+            error_threshold: 2,
+            initial_backoff: Duration::from_millis(5),
+            max_backoff: Duration::from_millis(20),
+            ..Default::default()
+        }
+    }
 
     #[test]
+    #[allow(deprecated)]
     fn test_state_transitions() {
+        use crate::CameraHandlers;
+
         let handler = CameraHandlers::NoOp;
         let source: String = "COM13".into();
 
@@ -230,4 +1129,356 @@ mod tests {
             .expect("disconnect should always succeed");
         assert!(matches!(camera.state, InternalState::Waiting(..)))
     }
+
+    #[test]
+    fn test_from_camera_handler() {
+        let mut camera = Camera::from_camera_handler(Box::new(NoOpCamera::init()), 30);
+        assert_eq!(camera.status(), CameraState::Disconnected);
+
+        camera
+            .connect("COM13".into())
+            .expect("no-op connect should always succeed");
+        assert_eq!(camera.status(), CameraState::Connected);
+        camera
+            .get_frame_timeout(Duration::from_secs(1))
+            .expect("injected handler should deliver frames");
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+    }
+
+    #[test]
+    fn test_frame_accessors() {
+        let mut camera = Camera::from_camera_handler(Box::new(NoOpCamera::init()), 100);
+
+        // every accessor refuses to hand out frames while disconnected
+        assert_eq!(
+            camera.try_get_frame().err(),
+            Some(CameraState::Disconnected)
+        );
+        assert_eq!(camera.latest_frame().err(), Some(CameraState::Disconnected));
+        assert_eq!(
+            camera.get_frame_timeout(Duration::from_millis(10)).err(),
+            Some(CameraState::Disconnected)
+        );
+
+        camera
+            .connect("noop".into())
+            .expect("no-op connect should always succeed");
+
+        let first = camera
+            .get_frame_timeout(Duration::from_secs(1))
+            .expect("no-op camera should deliver frames");
+
+        // let a few frames pile up, only the newest one should be returned
+        std::thread::sleep(Duration::from_millis(100));
+        let latest = camera
+            .latest_frame()
+            .expect("camera is connected")
+            .expect("frames should have been queued");
+        assert!(latest.sequence > first.sequence + 1);
+        assert!(
+            camera
+                .try_get_frame()
+                .expect("camera is connected")
+                .is_none()
+        );
+
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+    }
+
+    #[test]
+    fn test_capture_errors_are_surfaced() {
+        let handler = FailingCamera::init();
+        let attempts = handler.attempts.clone();
+
+        let mut camera = Camera::from_camera_handler(Box::new(handler), 1000);
+        assert_eq!(camera.status(), CameraState::Disconnected);
+
+        camera
+            .connect("failing".into())
+            .expect("connect should succeed");
+        std::thread::sleep(Duration::from_millis(300));
+        assert_eq!(camera.status(), CameraState::ReadFailed);
+
+        // a hot loop would rack up thousands of attempts in this time frame
+        let attempts = attempts.load(Ordering::Relaxed);
+        assert!(attempts < 20, "{attempts} capture attempts without backoff");
+
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+        assert_eq!(camera.status(), CameraState::Disconnected);
+    }
+
+    #[test]
+    fn test_supervisor_reconnects() {
+        let handler = FlakyCamera {
+            refused_connects: 3,
+            ..FlakyCamera::init()
+        };
+        let connects = handler.connects.clone();
+
+        let mut camera = Camera::from_camera_handler(Box::new(handler), 200);
+        camera.set_reconnect_policy(Some(fast_reconnect_policy()));
+        camera
+            .connect("flaky".into())
+            .expect("first connect should succeed");
+
+        // the initial batch, followed by the batch after reconnecting
+        for _ in 0..10 {
+            camera
+                .get_frame_timeout(Duration::from_secs(2))
+                .expect("supervisor should keep frames flowing");
+        }
+        assert!(connects.load(Ordering::Relaxed) >= 5);
+        assert_eq!(camera.status(), CameraState::Connected);
+
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+    }
+
+    #[test]
+    fn test_supervisor_gives_up() {
+        let handler = FlakyCamera {
+            refused_connects: usize::MAX,
+            ..FlakyCamera::init()
+        };
+        let connects = handler.connects.clone();
+
+        let mut camera = Camera::from_camera_handler(Box::new(handler), 200);
+        camera.set_reconnect_policy(Some(ReconnectPolicy {
+            max_attempts: Some(3),
+            ..fast_reconnect_policy()
+        }));
+        camera
+            .connect("flaky".into())
+            .expect("first connect should succeed");
+
+        // once the supervisor gives up, consumers are woken up
+        let result = loop {
+            match camera.get_frame() {
+                Ok(_) => continue,
+                Err(e) => break e,
+            }
+        };
+        assert_eq!(result, CameraState::Disconnected);
+        assert_eq!(camera.status(), CameraState::Disconnected);
+        assert_eq!(connects.load(Ordering::Relaxed), 4);
+
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+    }
+
+    #[test]
+    fn test_state_events() {
+        let mut camera = Camera::from_camera_handler(Box::new(NoOpCamera::init()), 30);
+        let events = camera.subscribe_events();
+
+        camera
+            .connect("noop".into())
+            .expect("no-op connect should always succeed");
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+
+        let events: Vec<_> = events.try_iter().collect();
+        let transitions: Vec<_> = events
+            .iter()
+            .map(|event| (event.previous.clone(), event.state.clone()))
+            .collect();
+        assert_eq!(
+            transitions,
+            [
+                (CameraState::Disconnected, CameraState::Connecting),
+                (CameraState::Connecting, CameraState::Connected),
+                (CameraState::Connected, CameraState::Disconnected),
+            ]
+        );
+        assert!(
+            events
+                .iter()
+                .all(|event| event.source.as_deref() == Some("noop"))
+        );
+        assert!(events.is_sorted_by_key(|event| event.timestamp));
+    }
+
+    #[test]
+    fn test_switch_source() {
+        let mut camera = Camera::from_camera_handler(Box::new(PickyCamera::init()), 100);
+        camera.set_backpressure_policy(BackpressurePolicy::Latest);
+        camera
+            .connect("first".into())
+            .expect("connect should succeed");
+        let events = camera.subscribe_events();
+        let next_sequence = |camera: &Camera| {
+            camera
+                .get_frame_timeout(Duration::from_secs(1))
+                .expect("frames should flow")
+                .sequence
+        };
+        let first = next_sequence(&camera);
+
+        camera
+            .switch_source("second".into())
+            .expect("switching to a valid source should succeed");
+        assert_eq!(camera.source().as_deref(), Some("second"));
+        let second = next_sequence(&camera);
+        assert!(second > first, "sequence restarted after switching");
+
+        // a failing source rolls back to the previous one
+        let result = camera.switch_source("bad".into());
+        assert_eq!(result, Err(CameraState::InvalidSource));
+        assert_eq!(camera.source().as_deref(), Some("second"));
+        assert_eq!(camera.status(), CameraState::Connected);
+        assert_eq!(camera.backpressure_policy(), BackpressurePolicy::Latest);
+        assert!(next_sequence(&camera) > second);
+
+        // subscribers survive the switches
+        let states: Vec<_> = events
+            .try_iter()
+            .filter(|event| event.source.as_deref() == Some("bad"))
+            .map(|event| event.state)
+            .collect();
+        assert_eq!(
+            states,
+            [CameraState::Connecting, CameraState::InvalidSource]
+        );
+
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+    }
+
+    #[test]
+    fn test_open_by_uri() {
+        let mut camera = Camera::open("noop://", 30).expect("no-op source should open");
+        assert_eq!(camera.status(), CameraState::Connected);
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+
+        assert_eq!(
+            Camera::open("gopher://camera", 30).err(),
+            Some(CameraState::InvalidSource)
+        );
+        assert_eq!(
+            Camera::open("COM13", 30).err(),
+            Some(CameraState::InvalidSource)
+        );
+    }
+
+    #[test]
+    fn test_corrupt_frames() {
+        let mut camera = Camera::from_camera_handler(Box::new(CorruptingCamera::init()), 200);
+        camera
+            .connect("corrupting".into())
+            .expect("corrupting backend should connect");
+
+        // corrupt frames are dropped by default
+        for _ in 0..4 {
+            let frame = camera
+                .get_frame_timeout(Duration::from_secs(1))
+                .expect("intact frames should be delivered");
+            assert_eq!(frame.corruption, None);
+            assert_eq!((frame.width, frame.height), (8, 8));
+        }
+        assert!(camera.rejected_frames() >= 3);
+
+        camera.set_corrupt_frame_policy(CorruptFramePolicy::Flag);
+        let flagged = std::iter::repeat_with(|| camera.get_frame_timeout(Duration::from_secs(1)))
+            .take(10)
+            .filter_map(Result::ok)
+            .find(|frame| frame.corruption.is_some())
+            .expect("flagged frames should be delivered");
+        assert_eq!(
+            flagged.corruption,
+            Some(crate::JpegError::MissingEndOfImage)
+        );
+
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+    }
+
+    #[test]
+    fn test_recording() {
+        let path = std::env::temp_dir().join(format!("etvr-{}-camera.etvr", std::process::id()));
+        let mut camera = Camera::open("noop://", 200).expect("no-op source should open");
+        assert!(camera.stop_recording().is_err());
+
+        camera
+            .start_recording(Recorder::create(&path).unwrap())
+            .expect("recording should start");
+        assert!(camera.is_recording());
+        let other = std::env::temp_dir().join(format!("etvr-{}-other.etvr", std::process::id()));
+        assert!(
+            camera
+                .start_recording(Recorder::create(&other).unwrap())
+                .is_err()
+        );
+        std::fs::remove_file(other).unwrap();
+
+        for _ in 0..5 {
+            camera
+                .get_frame_timeout(Duration::from_secs(1))
+                .expect("frames should be delivered while recording");
+        }
+        let summary = camera.stop_recording().expect("recording should finish");
+        assert!(!camera.is_recording());
+        assert!(summary.frames > 0);
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+
+        let mut recording = crate::Recording::open(&path).expect("recording should open");
+        assert!(recording.metadata().source.is_some());
+        assert_eq!(recording.metadata().backend.as_deref(), Some("noop"));
+        assert_eq!(recording.len() as u64, summary.frames);
+        assert!(recording.read_frame(0).is_ok());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_subscribers() {
+        let mut camera = Camera::open("synthetic://?width=16&height=16", 500)
+            .expect("synthetic source should open");
+        let tracker = camera.subscribe(8, BackpressurePolicy::Block);
+        let preview = camera.subscribe(1, BackpressurePolicy::Latest);
+
+        let frames: Vec<_> = (0..5)
+            .map(|_| tracker.get_frame_timeout(Duration::from_secs(1)).unwrap())
+            .collect();
+        assert!(
+            frames
+                .windows(2)
+                .all(|pair| pair[1].sequence == pair[0].sequence + 1)
+        );
+
+        // the preview only keeps the newest frame, shared with the tracker
+        std::thread::sleep(Duration::from_millis(20));
+        let latest = preview.get_frame_timeout(Duration::from_secs(1)).unwrap();
+        assert!(preview.dropped_frames().latest > 0);
+        let shared = std::iter::repeat_with(|| tracker.get_frame_timeout(Duration::from_secs(1)))
+            .map_while(Result::ok)
+            .find(|frame| frame.sequence == latest.sequence)
+            .expect("tracker should receive the same frame");
+        assert!(Arc::ptr_eq(&shared, &latest));
+
+        // nobody took frames from the camera's own queue, it kept the newest
+        for _ in 0..2 * BUFFERED_FRAMES {
+            tracker.get_frame_timeout(Duration::from_secs(1)).unwrap();
+        }
+        assert!(camera.dropped_frames().drop_oldest > 0);
+        assert!(camera.get_frame_timeout(Duration::from_secs(1)).is_ok());
+
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+        assert_eq!(tracker.get_frame().err(), Some(CameraState::Disconnected));
+    }
 }
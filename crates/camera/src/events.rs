@@ -0,0 +1,50 @@
+use std::{
+    sync::{Mutex, mpsc},
+    time::Instant,
+};
+
+use crate::CameraState;
+
+/// A state transition of a [`crate::Camera`] or its handler thread
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CameraEvent {
+    pub previous: CameraState,
+    pub state: CameraState,
+    /// The source the camera was connected to at the time of the transition
+    pub source: Option<String>,
+    pub timestamp: Instant,
+}
+
+/// Fans out events to all subscribers
+/// - Subscribers that dropped their receiver are pruned on the next event
+#[derive(Debug)]
+pub(crate) struct EventBus<T> {
+    subscribers: Mutex<Vec<mpsc::Sender<T>>>,
+}
+
+impl<T> Default for EventBus<T> {
+    fn default() -> Self {
+        Self {
+            subscribers: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<T: Clone> EventBus<T> {
+    pub fn subscribe(&self) -> mpsc::Receiver<T> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers
+            .lock()
+            .expect("event bus lock poisoned")
+            .push(tx);
+
+        rx
+    }
+
+    pub fn emit(&self, event: T) {
+        self.subscribers
+            .lock()
+            .expect("event bus lock poisoned")
+            .retain(|tx| tx.send(event.clone()).is_ok());
+    }
+}
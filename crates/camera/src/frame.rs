@@ -0,0 +1,133 @@
+use std::time::Instant;
+
+use crate::{CameraState, JpegError, jpeg};
+
+/// Layout of the bytes stored in [`Frame::data`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PixelFormat {
+    /// A complete JPEG image, including SOI/EOI markers
+    Jpeg,
+    /// 8 bit single channel grayscale
+    Gray8,
+    /// 8 bit per channel, interleaved in blue-green-red order (OpenCV default)
+    Bgr8,
+    /// Packed YUV 4:2:2, two bytes per pixel
+    Yuyv,
+}
+
+impl PixelFormat {
+    /// Returns the amount of bytes a single pixel occupies
+    /// - Returns `None` for compressed formats
+    pub fn bytes_per_pixel(&self) -> Option<usize> {
+        match self {
+            PixelFormat::Jpeg => None,
+            PixelFormat::Gray8 => Some(1),
+            PixelFormat::Bgr8 => Some(3),
+            PixelFormat::Yuyv => Some(2),
+        }
+    }
+
+    /// Whether the data is compressed and needs decoding before use
+    pub fn is_compressed(&self) -> bool {
+        self.bytes_per_pixel().is_none()
+    }
+}
+
+/// A single frame captured by a camera backend
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    /// Amount of bytes per row
+    /// - Always `0` for compressed formats
+    pub stride: u32,
+    pub format: PixelFormat,
+    /// Monotonic point in time at which the backend captured the frame
+    pub timestamp: Instant,
+    /// Monotonically increasing counter, assigned by [`crate::Camera`] per
+    /// delivered frame
+    /// - Keeps counting across reconnects and source switches for the
+    ///   lifetime of the camera
+    /// - Replayed frames keep the sequence they were recorded with
+    pub sequence: u64,
+    /// Set if the frame failed validation and was delivered anyway, see
+    /// [`crate::CorruptFramePolicy::Flag`]
+    pub corruption: Option<JpegError>,
+}
+
+impl Frame {
+    /// Creates a frame captured just now
+    pub fn new(data: Vec<u8>, width: u32, height: u32, stride: u32, format: PixelFormat) -> Frame {
+        Frame {
+            data,
+            width,
+            height,
+            stride,
+            format,
+            timestamp: Instant::now(),
+            sequence: 0,
+            corruption: None,
+        }
+    }
+
+    /// Creates an uncompressed frame with tightly packed rows
+    /// - Returns an error if the row size does not fit into the stride
+    pub fn packed(
+        data: Vec<u8>,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+    ) -> Result<Frame, CameraState> {
+        let stride = width
+            .checked_mul(format.bytes_per_pixel().unwrap_or(0) as u32)
+            .ok_or_else(|| CameraState::Error(format!("Frame width {width} is too large")))?;
+        Ok(Frame::new(data, width, height, stride, format))
+    }
+
+    /// Creates a frame from an encoded JPEG image
+    /// - Dimensions are read from the SOF segment, frames without a readable
+    ///   SOF segment are reported as `0x0`
+    pub fn jpeg(data: Vec<u8>) -> Frame {
+        let (width, height) = jpeg::dimensions(&data).unwrap_or((0, 0));
+        Frame::new(data, width, height, 0, PixelFormat::Jpeg)
+    }
+
+    /// Returns true if the frame holds no image data
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jpeg_dimensions() {
+        #[rustfmt::skip]
+        let data = vec![
+            0xFF, 0xD8,
+            // APP0 with a 2 byte payload
+            0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00,
+            // SOF0: precision 8, height 240, width 320, 1 component
+            0xFF, 0xC0, 0x00, 0x0B, 0x08, 0x00, 0xF0, 0x01, 0x40, 0x01, 0x01, 0x11, 0x00,
+            0xFF, 0xD9,
+        ];
+
+        let frame = Frame::jpeg(data);
+        assert_eq!((frame.width, frame.height), (320, 240));
+        assert_eq!(frame.stride, 0);
+
+        let frame = Frame::jpeg(vec![0xFF, 0xD8, 0xFF, 0xD9]);
+        assert_eq!((frame.width, frame.height), (0, 0));
+    }
+
+    #[test]
+    fn test_packed_stride() {
+        let frame = Frame::packed(vec![0; 4 * 2 * 3], 4, 2, PixelFormat::Bgr8).unwrap();
+        assert_eq!(frame.stride, 12);
+
+        assert!(Frame::packed(vec![], u32::MAX / 2, 1, PixelFormat::Bgr8).is_err());
+    }
+}
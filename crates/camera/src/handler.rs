@@ -1,5 +1,7 @@
 use std::fmt::Debug;
 
+use crate::Frame;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CameraState {
     Timeout,
@@ -8,6 +10,9 @@ pub enum CameraState {
     Connecting,
     Disconnected,
     InvalidSource,
+    /// The source has no more frames, e.g. a replay reached the end of its
+    /// recording
+    EndOfStream,
     Error(String),
 }
 
@@ -27,8 +32,17 @@ pub trait CameraHandler: Send + Sync + Debug {
     where
         Self: Sized;
 
-    // FIXME: this needs a concrete type
-    fn get_frame(&mut self) -> Result<Vec<u8>, CameraState>;
+    /// Captures the next frame from the camera
+    /// - The sequence number is assigned by [`crate::Camera`] and can be left
+    ///   at its default value
+    fn get_frame(&mut self) -> Result<Frame, CameraState>;
+
+    /// Whether frames already carry their sequence numbers, e.g. when
+    /// replaying a recording
+    /// - [`crate::Camera`] only numbers frames of other backends
+    fn keeps_sequence(&self) -> bool {
+        false
+    }
 
     /// Attempts to establish a connection to the camera
     /// - Backends should try to capture a single frame and discard it to ensure
@@ -0,0 +1,262 @@
+const SOI: [u8; 2] = [0xFF, 0xD8];
+
+/// How thoroughly JPEG frames are checked before they are handed out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JpegValidation {
+    /// Frames are passed on unchecked
+    Off,
+    /// SOI/EOI markers and the segment structure are checked, cheap enough
+    /// for every frame
+    #[default]
+    Structure,
+    /// Additionally decodes the whole image, catching corrupted entropy coded
+    /// data at the cost of a full decode per frame
+    #[cfg(feature = "jpeg-decode")]
+    Decode,
+}
+
+impl JpegValidation {
+    /// Checks the given JPEG image
+    pub fn validate(&self, data: &[u8]) -> Result<(), JpegError> {
+        match self {
+            JpegValidation::Off => Ok(()),
+            JpegValidation::Structure => check_structure(data),
+            #[cfg(feature = "jpeg-decode")]
+            JpegValidation::Decode => {
+                check_structure(data)?;
+                zune_jpeg::JpegDecoder::new(data)
+                    .decode()
+                    .map(|_| ())
+                    .map_err(|e| JpegError::Decode(format!("{e:?}")))
+            }
+        }
+    }
+}
+
+/// Determines what happens to frames failing [`JpegValidation`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CorruptFramePolicy {
+    /// Discard the frame
+    #[default]
+    Drop,
+    /// Deliver the frame with [`crate::Frame::corruption`] set
+    Flag,
+}
+
+/// Reason a JPEG image failed validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JpegError {
+    /// Data does not start with an SOI marker
+    MissingStartOfImage,
+    /// Data ends before the EOI marker, usually a truncated frame
+    MissingEndOfImage,
+    /// Malformed marker or segment at the given offset
+    InvalidSegment { offset: usize },
+    /// A scan starts before any SOF segment
+    MissingFrameHeader,
+    /// The image ends without containing any scan
+    MissingScan,
+    /// Non-padding bytes follow the EOI marker
+    TrailingData { len: usize },
+    /// The image could not be decoded
+    Decode(String),
+}
+
+/// Walks all segments, including the entropy coded data of each scan, up to
+/// the EOI marker
+fn check_structure(data: &[u8]) -> Result<(), JpegError> {
+    if !data.starts_with(&SOI) {
+        return Err(JpegError::MissingStartOfImage);
+    }
+
+    let mut pos = SOI.len();
+    let mut frame_header = false;
+    let mut scan = false;
+    loop {
+        let marker = match data.get(pos..pos + 2) {
+            Some(&[0xFF, marker]) => marker,
+            Some(_) => return Err(JpegError::InvalidSegment { offset: pos }),
+            None => return Err(JpegError::MissingEndOfImage),
+        };
+        let offset = pos;
+        pos += 2;
+
+        match marker {
+            // fill bytes
+            0xFF => pos -= 1,
+            0xD9 => break,
+            // standalone markers without a length field
+            0x01 | 0xD0..=0xD7 => {}
+            // stuffed zero or a nested SOI
+            0x00 | 0xD8 => return Err(JpegError::InvalidSegment { offset }),
+            _ => {
+                let len = match data.get(pos..pos + 2) {
+                    Some(&[high, low]) => u16::from_be_bytes([high, low]) as usize,
+                    _ => return Err(JpegError::MissingEndOfImage),
+                };
+                if len < 2 {
+                    return Err(JpegError::InvalidSegment { offset });
+                }
+                let segment = data
+                    .get(pos + 2..pos + len)
+                    .ok_or(JpegError::MissingEndOfImage)?;
+                pos += len;
+
+                if is_frame_header(marker) {
+                    if frame_size(segment).is_none_or(|(width, height)| width == 0 || height == 0) {
+                        return Err(JpegError::InvalidSegment { offset });
+                    }
+                    frame_header = true;
+                }
+
+                if marker == 0xDA {
+                    if !frame_header {
+                        return Err(JpegError::MissingFrameHeader);
+                    }
+                    scan = true;
+                    pos = skip_entropy_coded_data(data, pos).ok_or(JpegError::MissingEndOfImage)?;
+                }
+            }
+        }
+    }
+
+    if !scan {
+        return Err(JpegError::MissingScan);
+    }
+
+    // some encoders pad the buffer with zeros
+    let trailing = &data[pos..];
+    if trailing.iter().any(|&byte| byte != 0) {
+        return Err(JpegError::TrailingData {
+            len: trailing.len(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Returns the offset of the marker ending the entropy coded data
+fn skip_entropy_coded_data(data: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        pos += data.get(pos..)?.iter().position(|&byte| byte == 0xFF)?;
+        match *data.get(pos + 1)? {
+            // stuffed zero, restart markers and fill bytes are part of the scan
+            0x00 | 0xD0..=0xD7 | 0xFF => pos += 1,
+            _ => return Some(pos),
+        }
+    }
+}
+
+/// SOF0..SOF15, except DHT (C4), JPG (C8) and DAC (CC)
+fn is_frame_header(marker: u8) -> bool {
+    matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC)
+}
+
+/// Reads `(width, height)` from the payload of an SOF segment
+fn frame_size(segment: &[u8]) -> Option<(u32, u32)> {
+    let segment = segment.get(..5)?;
+    let height = u16::from_be_bytes([segment[1], segment[2]]);
+    let width = u16::from_be_bytes([segment[3], segment[4]]);
+    Some((width as u32, height as u32))
+}
+
+/// Walks the JPEG segments until the first start-of-frame marker and returns
+/// its `(width, height)`
+pub(crate) fn dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    if data.get(..2)? != SOI {
+        return None;
+    }
+
+    let mut pos = 2;
+    loop {
+        if *data.get(pos)? != 0xFF {
+            return None;
+        }
+        let marker = *data.get(pos + 1)?;
+        pos += 2;
+
+        match marker {
+            // fill bytes
+            0xFF => pos -= 1,
+            // standalone markers without a length field
+            0x01 | 0xD0..=0xD7 => {}
+            _ if is_frame_header(marker) => return frame_size(data.get(pos + 2..)?),
+            // start of scan or end of image without any frame header
+            0xDA | 0xD9 => return None,
+            _ => {
+                let len = u16::from_be_bytes([*data.get(pos)?, *data.get(pos + 1)?]) as usize;
+                pos += len;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 8x8 grayscale baseline image
+    const GRAY_8X8: &[u8] = include_bytes!("../tests/data/gray_8x8.jpg");
+
+    #[test]
+    fn test_valid_structure() {
+        assert_eq!(JpegValidation::Structure.validate(GRAY_8X8), Ok(()));
+        assert_eq!(dimensions(GRAY_8X8), Some((8, 8)));
+
+        let padded = [GRAY_8X8, &[0; 16]].concat();
+        assert_eq!(JpegValidation::Structure.validate(&padded), Ok(()));
+    }
+
+    #[test]
+    fn test_corrupt_structure() {
+        let validate = |data: &[u8]| JpegValidation::Structure.validate(data);
+
+        assert_eq!(validate(&[]), Err(JpegError::MissingStartOfImage));
+        assert_eq!(
+            validate(&GRAY_8X8[2..]),
+            Err(JpegError::MissingStartOfImage)
+        );
+        for len in [2, 100, GRAY_8X8.len() - 2] {
+            assert_eq!(
+                validate(&GRAY_8X8[..len]),
+                Err(JpegError::MissingEndOfImage),
+                "truncated to {len} bytes"
+            );
+        }
+
+        let trailing = [GRAY_8X8, b"garbage"].concat();
+        assert_eq!(validate(&trailing), Err(JpegError::TrailingData { len: 7 }));
+
+        assert_eq!(
+            validate(&[0xFF, 0xD8, 0xFF, 0xD9]),
+            Err(JpegError::MissingScan)
+        );
+        let no_frame_header = [0xFF, 0xD8, 0xFF, 0xDA, 0x00, 0x02, 0x00, 0xFF, 0xD9];
+        assert_eq!(
+            validate(&no_frame_header),
+            Err(JpegError::MissingFrameHeader)
+        );
+
+        let mut broken_marker = GRAY_8X8.to_vec();
+        broken_marker[20] = 0x00;
+        assert_eq!(
+            validate(&broken_marker),
+            Err(JpegError::InvalidSegment { offset: 20 })
+        );
+    }
+
+    #[cfg(feature = "jpeg-decode")]
+    #[test]
+    fn test_decode() {
+        assert_eq!(JpegValidation::Decode.validate(GRAY_8X8), Ok(()));
+
+        // structurally sound, but the huffman table is gone
+        let mut data = GRAY_8X8.to_vec();
+        let dht = data.windows(2).position(|w| w == [0xFF, 0xC4]).unwrap();
+        data[dht + 1] = 0xEE;
+        assert!(matches!(
+            JpegValidation::Decode.validate(&data),
+            Err(JpegError::Decode(_))
+        ));
+    }
+}
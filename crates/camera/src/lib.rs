@@ -1,7 +1,34 @@
 mod backends;
+mod broadcast;
 mod camera;
+mod events;
+mod frame;
 mod handler;
+mod jpeg;
+mod queue;
+mod recorder;
+mod registry;
+mod source;
+mod supervisor;
 
+#[allow(deprecated)]
 pub use backends::CameraHandlers;
-pub use camera::Camera;
+pub use backends::{
+    BaudRate, DeviceEvent, DeviceInfo, EspFlasher, EyeState, FirmwarePolicy, FirmwareVersion,
+    FlashError, FlashProgress, GazeScript, GroundTruth, MjpegHttpCamera, NoOpCamera, OpenCVCamera,
+    OpenIrisCamera, OpenIrisCommand, OpenIrisCommander, OpenIrisDecoder, OpenIrisDevice,
+    OpenIrisDiscovery, OpenIrisMessage, PortOpener, ProvisioningError, ReplayCamera, ReplayControl,
+    ReplayMode, ResetMode, SerialConfig, SerialWatcher, StreamingMode, SyntheticEye,
+    SyntheticEyeCamera, UsbBridge, WifiProvisioning, WifiStatus,
+};
+pub use broadcast::FrameSubscription;
+pub use camera::{Camera, CameraControl};
+pub use events::CameraEvent;
+pub use frame::{Frame, PixelFormat};
 pub use handler::*;
+pub use jpeg::{CorruptFramePolicy, JpegError, JpegValidation};
+pub use queue::{BackpressurePolicy, DroppedFrames};
+pub use recorder::{Recorder, Recording, RecordingMetadata, RecordingSummary};
+pub use registry::{BackendFactory, BackendRegistry};
+pub use source::CameraSource;
+pub use supervisor::ReconnectPolicy;
@@ -0,0 +1,346 @@
+use std::{
+    collections::VecDeque,
+    sync::{Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// Determines what happens once a consumer falls behind and the frame queue is
+/// full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BackpressurePolicy {
+    /// Block the capture thread until the consumer takes a frame
+    #[default]
+    Block,
+    /// Evict the oldest queued frame to make room for the new one
+    DropOldest,
+    /// Discard the new frame and keep the queued ones
+    DropNewest,
+    /// Single slot mailbox, every new frame replaces the queued one
+    Latest,
+}
+
+/// Amount of frames discarded by each [`BackpressurePolicy`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DroppedFrames {
+    pub drop_oldest: u64,
+    pub drop_newest: u64,
+    pub latest: u64,
+    /// Frames discarded because switching the policy shrank the queue
+    pub trimmed: u64,
+}
+
+impl DroppedFrames {
+    pub fn total(&self) -> u64 {
+        self.drop_oldest + self.drop_newest + self.latest + self.trimmed
+    }
+}
+
+#[derive(Debug)]
+struct Inner<T> {
+    items: VecDeque<T>,
+    capacity: usize,
+    policy: BackpressurePolicy,
+    closed: bool,
+    dropped: DroppedFrames,
+}
+
+impl<T> Inner<T> {
+    fn capacity(&self) -> usize {
+        match self.policy {
+            BackpressurePolicy::Latest => 1,
+            _ => self.capacity,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.items.len() >= self.capacity()
+    }
+}
+
+/// Bounded multi-producer multi-consumer queue, handing frames from the
+/// capture thread to consumers according to a [`BackpressurePolicy`]
+/// - Closing the queue wakes up all blocked producers and consumers
+#[derive(Debug)]
+pub(crate) struct FrameQueue<T> {
+    inner: Mutex<Inner<T>>,
+    not_empty: Condvar,
+    not_full: Condvar,
+}
+
+impl<T> FrameQueue<T> {
+    pub fn new(capacity: usize, policy: BackpressurePolicy) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                items: VecDeque::with_capacity(capacity),
+                capacity: capacity.max(1),
+                policy,
+                closed: false,
+                dropped: DroppedFrames::default(),
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner<T>> {
+        self.inner.lock().expect("frame queue lock poisoned")
+    }
+
+    /// Queues an item, applying the current policy if the queue is full
+    /// - Returns the item if the queue has been closed
+    pub fn push(&self, item: T) -> Result<(), T> {
+        self.enqueue(item, true)
+    }
+
+    /// Queues an item unless it has to wait for room under
+    /// [`BackpressurePolicy::Block`]
+    /// - Returns the item if the queue is full or has been closed
+    pub fn try_push(&self, item: T) -> Result<(), T> {
+        self.enqueue(item, false)
+    }
+
+    fn enqueue(&self, item: T, wait: bool) -> Result<(), T> {
+        let mut inner = self.lock();
+
+        while !inner.closed && inner.is_full() {
+            match inner.policy {
+                BackpressurePolicy::Block if !wait => return Err(item),
+                BackpressurePolicy::Block => {
+                    inner = self
+                        .not_full
+                        .wait(inner)
+                        .expect("frame queue lock poisoned");
+                }
+                BackpressurePolicy::DropOldest => {
+                    inner.items.pop_front();
+                    inner.dropped.drop_oldest += 1;
+                }
+                BackpressurePolicy::DropNewest => {
+                    inner.dropped.drop_newest += 1;
+                    return Ok(());
+                }
+                BackpressurePolicy::Latest => {
+                    inner.items.pop_front();
+                    inner.dropped.latest += 1;
+                }
+            }
+        }
+
+        if inner.closed {
+            return Err(item);
+        }
+
+        inner.items.push_back(item);
+        self.not_empty.notify_one();
+
+        Ok(())
+    }
+
+    /// Takes the oldest item, blocking until one is available
+    /// - Returns `None` once the queue has been closed
+    pub fn pop(&self) -> Option<T> {
+        let mut inner = self.lock();
+
+        loop {
+            if let Some(item) = self.take(&mut inner) {
+                return Some(item);
+            }
+            if inner.closed {
+                return None;
+            }
+            inner = self
+                .not_empty
+                .wait(inner)
+                .expect("frame queue lock poisoned");
+        }
+    }
+
+    /// Takes the oldest item, waiting at most `timeout`
+    /// - Returns `Err(())` once the queue has been closed
+    pub fn pop_timeout(&self, timeout: Duration) -> Result<Option<T>, ()> {
+        let deadline = Instant::now() + timeout;
+        let mut inner = self.lock();
+
+        loop {
+            if let Some(item) = self.take(&mut inner) {
+                return Ok(Some(item));
+            }
+            if inner.closed {
+                return Err(());
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(None);
+            }
+            inner = self
+                .not_empty
+                .wait_timeout(inner, deadline - now)
+                .expect("frame queue lock poisoned")
+                .0;
+        }
+    }
+
+    /// Takes the oldest item without blocking
+    /// - Returns `Err(())` if the queue is empty and has been closed
+    pub fn try_pop(&self) -> Result<Option<T>, ()> {
+        self.pop_timeout(Duration::ZERO)
+    }
+
+    /// Empties the queue and returns only the newest item
+    /// - Returns `Err(())` if the queue is empty and has been closed
+    pub fn drain_latest(&self) -> Result<Option<T>, ()> {
+        let mut inner = self.lock();
+        let latest = inner.items.drain(..).next_back();
+        self.not_full.notify_all();
+
+        match latest {
+            None if inner.closed => Err(()),
+            latest => Ok(latest),
+        }
+    }
+
+    fn take(&self, inner: &mut Inner<T>) -> Option<T> {
+        let item = inner.items.pop_front()?;
+        self.not_full.notify_one();
+        Some(item)
+    }
+
+    /// Closes the queue, wakes up everyone waiting and drops all queued items
+    pub fn close(&self) {
+        let mut inner = self.lock();
+        inner.closed = true;
+        inner.items.clear();
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+
+    /// Closes the queue like [`Self::close`] but leaves the queued items to
+    /// the consumers
+    pub fn finish(&self) {
+        self.lock().closed = true;
+        self.not_empty.notify_all();
+        self.not_full.notify_all();
+    }
+
+    /// Reopens a previously closed queue
+    pub fn reopen(&self) {
+        self.lock().closed = false;
+    }
+
+    pub fn policy(&self) -> BackpressurePolicy {
+        self.lock().policy
+    }
+
+    /// Switches the policy, the oldest queued items exceeding the new
+    /// capacity are dropped and counted as trimmed
+    pub fn set_policy(&self, policy: BackpressurePolicy) {
+        let mut inner = self.lock();
+        inner.policy = policy;
+
+        while inner.items.len() > inner.capacity() {
+            inner.items.pop_front();
+            inner.dropped.trimmed += 1;
+        }
+        self.not_full.notify_all();
+    }
+
+    pub fn dropped(&self) -> DroppedFrames {
+        self.lock().dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn test_drop_policies() {
+        let queue = FrameQueue::new(2, BackpressurePolicy::DropOldest);
+        (0..5).for_each(|i| queue.push(i).unwrap());
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.pop(), Some(4));
+        assert_eq!(queue.dropped().drop_oldest, 3);
+
+        let queue = FrameQueue::new(2, BackpressurePolicy::DropNewest);
+        (0..5).for_each(|i| queue.push(i).unwrap());
+        assert_eq!(queue.pop(), Some(0));
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.dropped().drop_newest, 3);
+
+        let queue = FrameQueue::new(2, BackpressurePolicy::Latest);
+        (0..5).for_each(|i| queue.push(i).unwrap());
+        assert_eq!(queue.try_pop(), Ok(Some(4)));
+        assert_eq!(queue.try_pop(), Ok(None));
+        assert_eq!(queue.dropped().latest, 4);
+        assert_eq!(queue.dropped().total(), 4);
+    }
+
+    #[test]
+    fn test_set_policy_trims() {
+        let queue = FrameQueue::new(3, BackpressurePolicy::Block);
+        (0..3).for_each(|i| queue.push(i).unwrap());
+
+        queue.set_policy(BackpressurePolicy::Latest);
+        assert_eq!(queue.try_pop(), Ok(Some(2)));
+        assert_eq!(
+            queue.dropped(),
+            DroppedFrames {
+                trimmed: 2,
+                ..DroppedFrames::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_block_until_consumed() {
+        let queue = Arc::new(FrameQueue::new(1, BackpressurePolicy::Block));
+        queue.push(0).unwrap();
+
+        let producer = {
+            let queue = queue.clone();
+            std::thread::spawn(move || queue.push(1))
+        };
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!producer.is_finished());
+
+        assert_eq!(queue.pop(), Some(0));
+        producer.join().unwrap().unwrap();
+        assert_eq!(queue.pop(), Some(1));
+        assert_eq!(queue.dropped().total(), 0);
+    }
+
+    #[test]
+    fn test_close_wakes_up_blocked_producer() {
+        let queue = Arc::new(FrameQueue::new(1, BackpressurePolicy::Block));
+        queue.push(0).unwrap();
+
+        let producer = {
+            let queue = queue.clone();
+            std::thread::spawn(move || queue.push(1))
+        };
+
+        std::thread::sleep(Duration::from_millis(50));
+        queue.close();
+
+        assert_eq!(producer.join().unwrap(), Err(1));
+        assert_eq!(queue.pop(), None);
+        assert_eq!(queue.try_pop(), Err(()));
+    }
+
+    #[test]
+    fn test_finish_keeps_items() {
+        let queue = FrameQueue::new(4, BackpressurePolicy::Block);
+        queue.push(0).unwrap();
+        queue.push(1).unwrap();
+        queue.finish();
+
+        assert_eq!(queue.push(2), Err(2));
+        assert_eq!(queue.pop(), Some(0));
+        assert_eq!(queue.try_pop(), Ok(Some(1)));
+        assert_eq!(queue.pop(), None);
+    }
+}
@@ -0,0 +1,747 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{Compression, read::DeflateDecoder, write::DeflateEncoder};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{CameraState, Frame, FrameSubscription, PixelFormat};
+
+// Container layout, all integers little endian:
+// header:  magic, metadata length (u32), metadata as JSON
+// record:  timestamp in µs since the first frame (u64), sequence (u64),
+//          format (u8), encoding (u8), width, height, stride, data length
+//          (u32 each), data
+// index:   per record its timestamp and file offset (u64 each)
+// trailer: index offset (u64), record count (u64), index magic
+// The index is written once the recording is finished, recordings cut short
+// are recovered by scanning the records.
+
+const MAGIC: &[u8; 8] = b"ETVRREC1";
+const INDEX_MAGIC: &[u8; 8] = b"ETVRIDX1";
+const RECORD_HEADER_LEN: u64 = 8 + 8 + 1 + 1 + 4 * 4;
+const INDEX_ENTRY_LEN: u64 = 16;
+const TRAILER_LEN: u64 = 8 + 8 + INDEX_MAGIC.len() as u64;
+
+const ENCODING_STORED: u8 = 0;
+const ENCODING_DEFLATE: u8 = 1;
+
+// frames the recorder thread may fall behind by before skipping the oldest
+pub(crate) const RECORDER_BUFFER: usize = 30;
+// how often the recorder thread checks whether it should stop
+const RECORDER_POLL: Duration = Duration::from_millis(20);
+
+/// Information about a recording, stored in its header
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordingMetadata {
+    /// Source the camera is connected to, filled in by
+    /// [`crate::Camera::start_recording`]
+    pub source: Option<String>,
+    /// Name of the backend, e.g. `openiris`
+    pub backend: Option<String>,
+    /// Firmware version of the device, if known
+    pub firmware: Option<String>,
+    /// Wall clock time the recording started at, in milliseconds since the
+    /// unix epoch
+    pub started_at: u64,
+}
+
+/// Outcome of a finished recording
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingSummary {
+    pub path: PathBuf,
+    pub frames: u64,
+    /// Size of the file including header and index
+    pub bytes: u64,
+    /// Time between the first and the last frame
+    pub duration: Duration,
+    /// Whether frames were left out after reaching the size limit
+    pub truncated: bool,
+    /// Frames left out because writing could not keep up with the camera
+    pub skipped: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct IndexEntry {
+    timestamp: u64,
+    offset: u64,
+}
+
+/// Writes frames to a recording file, see [`crate::Camera::start_recording`]
+/// - JPEG frames are stored as is, uncompressed frames are deflated
+/// - The file is finished once recording stops or the recorder is dropped
+#[derive(Debug)]
+pub struct Recorder {
+    path: PathBuf,
+    file: BufWriter<File>,
+    metadata: RecordingMetadata,
+    max_size: Option<u64>,
+    compression: Compression,
+    header_written: bool,
+    written: u64,
+    index: Vec<IndexEntry>,
+    first_timestamp: Option<Instant>,
+    last_timestamp: u64,
+    truncated: bool,
+    skipped: u64,
+    failed: Option<CameraState>,
+    finished: bool,
+}
+
+impl Recorder {
+    /// Creates the recording file, replacing an existing one
+    pub fn create(path: impl AsRef<Path>) -> Result<Recorder, CameraState> {
+        let path = path.as_ref().to_path_buf();
+        let file = File::create(&path)
+            .map_err(|e| CameraState::Error(format!("Failed to create {}: {e}", path.display())))?;
+
+        Ok(Recorder {
+            path,
+            file: BufWriter::new(file),
+            metadata: RecordingMetadata::default(),
+            max_size: None,
+            compression: Compression::fast(),
+            header_written: false,
+            written: 0,
+            index: Vec::new(),
+            first_timestamp: None,
+            last_timestamp: 0,
+            truncated: false,
+            skipped: 0,
+            failed: None,
+            finished: false,
+        })
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.metadata.source = Some(source.into());
+        self
+    }
+
+    pub fn with_backend(mut self, backend: impl Into<String>) -> Self {
+        self.metadata.backend = Some(backend.into());
+        self
+    }
+
+    pub fn with_firmware(mut self, firmware: impl Into<String>) -> Self {
+        self.metadata.firmware = Some(firmware.into());
+        self
+    }
+
+    /// Stops recording once the file would grow beyond the given size,
+    /// header and index included
+    pub fn with_max_size(mut self, bytes: u64) -> Self {
+        self.max_size = Some(bytes);
+        self
+    }
+
+    /// Whether frames are still being written
+    /// - False once the size limit was reached or writing failed
+    pub fn is_recording(&self) -> bool {
+        !self.finished && !self.truncated && self.failed.is_none()
+    }
+
+    pub(crate) fn metadata_mut(&mut self) -> &mut RecordingMetadata {
+        &mut self.metadata
+    }
+
+    /// Appends the frame
+    /// - Frames are silently skipped once the recorder stopped recording
+    pub fn write(&mut self, frame: &Frame) -> Result<(), CameraState> {
+        if !self.is_recording() {
+            return Ok(());
+        }
+
+        let result = self.write_record(frame).map_err(|e| {
+            warn!("failed to write to {}: {e}", self.path.display());
+            CameraState::Error(format!("Failed to write recording: {e}"))
+        });
+        if let Err(e) = &result {
+            self.failed = Some(e.clone());
+        }
+        result
+    }
+
+    /// Counts frames that never reached the recorder
+    pub(crate) fn skip(&mut self, frames: u64) {
+        self.skipped += frames;
+    }
+
+    /// Writes the index and flushes the file
+    pub fn finish(mut self) -> Result<RecordingSummary, CameraState> {
+        self.finalize()
+    }
+
+    fn write_record(&mut self, frame: &Frame) -> io::Result<()> {
+        self.write_header()?;
+
+        let (encoding, data) = match frame.format {
+            PixelFormat::Jpeg => (ENCODING_STORED, frame.data.clone()),
+            _ => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), self.compression);
+                encoder.write_all(&frame.data)?;
+                (ENCODING_DEFLATE, encoder.finish()?)
+            }
+        };
+
+        let record_len = RECORD_HEADER_LEN + data.len() as u64;
+        // room for this record and the index entries including its own
+        let finished_len = self.written
+            + record_len
+            + (self.index.len() as u64 + 1) * INDEX_ENTRY_LEN
+            + TRAILER_LEN;
+        if self
+            .max_size
+            .is_some_and(|max_size| finished_len > max_size)
+        {
+            info!("recording {} reached its size limit", self.path.display());
+            self.truncated = true;
+            return Ok(());
+        }
+
+        let first = *self.first_timestamp.get_or_insert(frame.timestamp);
+        let timestamp = frame.timestamp.saturating_duration_since(first).as_micros() as u64;
+
+        let mut header = Vec::with_capacity(RECORD_HEADER_LEN as usize);
+        header.extend(timestamp.to_le_bytes());
+        header.extend(frame.sequence.to_le_bytes());
+        header.push(format_id(frame.format));
+        header.push(encoding);
+        for value in [frame.width, frame.height, frame.stride, data.len() as u32] {
+            header.extend(value.to_le_bytes());
+        }
+        self.file.write_all(&header)?;
+        self.file.write_all(&data)?;
+
+        self.index.push(IndexEntry {
+            timestamp,
+            offset: self.written,
+        });
+        self.written += record_len;
+        self.last_timestamp = timestamp;
+
+        Ok(())
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        if self.header_written {
+            return Ok(());
+        }
+
+        self.metadata.started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis() as u64);
+        let metadata = serde_json::to_vec(&self.metadata)?;
+
+        self.file.write_all(MAGIC)?;
+        self.file
+            .write_all(&(metadata.len() as u32).to_le_bytes())?;
+        self.file.write_all(&metadata)?;
+        self.written = (MAGIC.len() + 4 + metadata.len()) as u64;
+        self.header_written = true;
+
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<RecordingSummary, CameraState> {
+        if let Some(e) = self.failed.clone() {
+            return Err(e);
+        }
+        if self.finished {
+            return Err(CameraState::Error("Recording already finished".to_string()));
+        }
+        self.finished = true;
+
+        let finish = |recorder: &mut Recorder| -> io::Result<()> {
+            recorder.write_header()?;
+
+            let index_offset = recorder.written;
+            for entry in &recorder.index {
+                recorder.file.write_all(&entry.timestamp.to_le_bytes())?;
+                recorder.file.write_all(&entry.offset.to_le_bytes())?;
+            }
+            recorder.file.write_all(&index_offset.to_le_bytes())?;
+            recorder
+                .file
+                .write_all(&(recorder.index.len() as u64).to_le_bytes())?;
+            recorder.file.write_all(INDEX_MAGIC)?;
+            recorder.file.flush()?;
+
+            recorder.written += recorder.index.len() as u64 * INDEX_ENTRY_LEN + TRAILER_LEN;
+            Ok(())
+        };
+        finish(self).map_err(|e| {
+            warn!("failed to finish {}: {e}", self.path.display());
+            CameraState::Error(format!("Failed to finish recording: {e}"))
+        })?;
+
+        let summary = RecordingSummary {
+            path: self.path.clone(),
+            frames: self.index.len() as u64,
+            bytes: self.written,
+            duration: Duration::from_micros(self.last_timestamp),
+            truncated: self.truncated,
+            skipped: self.skipped,
+        };
+        info!(
+            "finished recording {} with {} frames",
+            summary.path.display(),
+            summary.frames
+        );
+        Ok(summary)
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if !self.finished && self.failed.is_none() {
+            let _ = self.finalize();
+        }
+    }
+}
+
+/// Writes the frames of a subscription on a thread of its own, keeping
+/// compression and disk writes off the capture thread
+/// - Frames are skipped once the thread falls behind, see
+///   [`RecordingSummary::skipped`]
+#[derive(Debug)]
+pub(crate) struct RecorderThread {
+    stop: Arc<AtomicBool>,
+    recording: Arc<AtomicBool>,
+    thread: Option<std::thread::JoinHandle<Recorder>>,
+}
+
+impl RecorderThread {
+    pub fn spawn(mut recorder: Recorder, frames: FrameSubscription) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let recording = Arc::new(AtomicBool::new(recorder.is_recording()));
+
+        let thread = {
+            let stop = stop.clone();
+            let recording = recording.clone();
+            std::thread::spawn(move || {
+                // write failures are remembered by the recorder and reported
+                // once recording stops
+                let mut write = |frame: &Frame| {
+                    let _ = recorder.write(frame);
+                    recording.store(recorder.is_recording(), Ordering::Relaxed);
+                };
+
+                while !stop.load(Ordering::Relaxed) {
+                    match frames.get_frame_timeout(RECORDER_POLL) {
+                        Ok(frame) => write(&frame),
+                        Err(CameraState::Timeout) => {}
+                        // closed while the camera is disconnected
+                        Err(_) => std::thread::sleep(RECORDER_POLL),
+                    }
+                }
+
+                // frames queued before stopping still belong to the recording
+                std::iter::from_fn(|| frames.try_get_frame().ok().flatten())
+                    .take(RECORDER_BUFFER)
+                    .for_each(|frame| write(&frame));
+
+                recorder.skip(frames.dropped_frames().total());
+                recorder
+            })
+        };
+
+        Self {
+            stop,
+            recording,
+            thread: Some(thread),
+        }
+    }
+
+    /// See [`Recorder::is_recording`]
+    pub fn is_recording(&self) -> bool {
+        self.recording.load(Ordering::Relaxed)
+    }
+
+    /// Writes the frames still queued and finishes the file
+    pub fn finish(mut self) -> Result<RecordingSummary, CameraState> {
+        self.join().finish()
+    }
+
+    fn join(&mut self) -> Recorder {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread
+            .take()
+            .expect("recorder thread is only joined once")
+            .join()
+            .expect("recorder thread has panicked")
+    }
+}
+
+impl Drop for RecorderThread {
+    fn drop(&mut self) {
+        if self.thread.is_some() {
+            // dropping the recorder finishes the file
+            self.join();
+        }
+    }
+}
+
+/// A recording opened for reading
+#[derive(Debug)]
+pub struct Recording {
+    file: BufReader<File>,
+    metadata: RecordingMetadata,
+    index: Vec<IndexEntry>,
+}
+
+impl Recording {
+    /// Opens a recording, recovering the index of recordings that were cut
+    /// short
+    pub fn open(path: impl AsRef<Path>) -> Result<Recording, CameraState> {
+        let path = path.as_ref();
+        let invalid = |reason: String| {
+            warn!("invalid recording {}: {reason}", path.display());
+            CameraState::InvalidSource
+        };
+
+        let file = File::open(path).map_err(|e| invalid(e.to_string()))?;
+        let len = file.metadata().map_err(|e| invalid(e.to_string()))?.len();
+        let mut file = BufReader::new(file);
+
+        let mut magic = [0u8; 8];
+        file.read_exact(&mut magic)
+            .map_err(|e| invalid(e.to_string()))?;
+        if &magic != MAGIC {
+            return Err(invalid("not a recording".to_string()));
+        }
+        let metadata_len = read_u32(&mut file).map_err(|e| invalid(e.to_string()))?;
+        let mut metadata = vec![0u8; metadata_len as usize];
+        file.read_exact(&mut metadata)
+            .map_err(|e| invalid(e.to_string()))?;
+        let metadata = serde_json::from_slice(&metadata).map_err(|e| invalid(e.to_string()))?;
+
+        let records_start = 12 + metadata_len as u64;
+        let index = match read_index(&mut file, len) {
+            Ok(Some(index)) => index,
+            Ok(None) | Err(_) => {
+                warn!("recording {} has no index, scanning", path.display());
+                scan_records(&mut file, records_start, len).map_err(|e| invalid(e.to_string()))?
+            }
+        };
+
+        Ok(Recording {
+            file,
+            metadata,
+            index,
+        })
+    }
+
+    pub fn metadata(&self) -> &RecordingMetadata {
+        &self.metadata
+    }
+
+    /// Returns the amount of frames
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Returns the time of the frame relative to the first one
+    pub fn timestamp(&self, index: usize) -> Option<Duration> {
+        self.index
+            .get(index)
+            .map(|entry| Duration::from_micros(entry.timestamp))
+    }
+
+    /// Returns the index of the last frame at or before the given time
+    pub fn position(&self, timestamp: Duration) -> usize {
+        let timestamp = timestamp.as_micros() as u64;
+        self.index
+            .partition_point(|entry| entry.timestamp <= timestamp)
+            .saturating_sub(1)
+    }
+
+    /// Reads the frame at the given index
+    /// - The timestamp of the frame is the time of reading, the recorded time
+    ///   is returned by [`Recording::timestamp`]
+    pub fn read_frame(&mut self, index: usize) -> Result<Frame, CameraState> {
+        let Some(entry) = self.index.get(index).copied() else {
+            return Err(CameraState::Error(format!("No frame at index {index}")));
+        };
+
+        let mut read = || -> io::Result<Frame> {
+            self.file.seek(SeekFrom::Start(entry.offset))?;
+            let record = read_record_header(&mut self.file)?;
+            let mut data = vec![0u8; record.len as usize];
+            self.file.read_exact(&mut data)?;
+
+            if record.encoding == ENCODING_DEFLATE {
+                let mut decoded = Vec::new();
+                DeflateDecoder::new(data.as_slice()).read_to_end(&mut decoded)?;
+                data = decoded;
+            }
+
+            let mut frame = Frame::new(
+                data,
+                record.width,
+                record.height,
+                record.stride,
+                record.format,
+            );
+            frame.sequence = record.sequence;
+            Ok(frame)
+        };
+
+        read().map_err(|e| {
+            warn!("failed to read frame {index}: {e}");
+            CameraState::ReadFailed
+        })
+    }
+}
+
+struct RecordHeader {
+    timestamp: u64,
+    sequence: u64,
+    format: PixelFormat,
+    encoding: u8,
+    width: u32,
+    height: u32,
+    stride: u32,
+    len: u32,
+}
+
+fn format_id(format: PixelFormat) -> u8 {
+    match format {
+        PixelFormat::Jpeg => 0,
+        PixelFormat::Gray8 => 1,
+        PixelFormat::Bgr8 => 2,
+        PixelFormat::Yuyv => 3,
+    }
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+fn read_record_header(reader: &mut impl Read) -> io::Result<RecordHeader> {
+    let timestamp = read_u64(reader)?;
+    let sequence = read_u64(reader)?;
+    let mut ids = [0u8; 2];
+    reader.read_exact(&mut ids)?;
+    let format = match ids[0] {
+        0 => PixelFormat::Jpeg,
+        1 => PixelFormat::Gray8,
+        2 => PixelFormat::Bgr8,
+        3 => PixelFormat::Yuyv,
+        id => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown pixel format {id}"),
+            ));
+        }
+    };
+
+    Ok(RecordHeader {
+        timestamp,
+        sequence,
+        format,
+        encoding: ids[1],
+        width: read_u32(reader)?,
+        height: read_u32(reader)?,
+        stride: read_u32(reader)?,
+        len: read_u32(reader)?,
+    })
+}
+
+/// Reads the index from the end of the file
+/// - Returns `None` if the recording was not finished
+fn read_index(file: &mut BufReader<File>, len: u64) -> io::Result<Option<Vec<IndexEntry>>> {
+    let Some(trailer_offset) = len.checked_sub(TRAILER_LEN) else {
+        return Ok(None);
+    };
+    file.seek(SeekFrom::Start(trailer_offset))?;
+    let index_offset = read_u64(file)?;
+    let count = read_u64(file)?;
+    let mut magic = [0u8; 8];
+    file.read_exact(&mut magic)?;
+    if &magic != INDEX_MAGIC || index_offset + count * INDEX_ENTRY_LEN != trailer_offset {
+        return Ok(None);
+    }
+
+    file.seek(SeekFrom::Start(index_offset))?;
+    let index = (0..count)
+        .map(|_| {
+            Ok(IndexEntry {
+                timestamp: read_u64(file)?,
+                offset: read_u64(file)?,
+            })
+        })
+        .collect::<io::Result<_>>()?;
+    Ok(Some(index))
+}
+
+/// Rebuilds the index by walking the records, stopping at the first
+/// incomplete one
+fn scan_records(file: &mut BufReader<File>, start: u64, len: u64) -> io::Result<Vec<IndexEntry>> {
+    let mut index = Vec::new();
+    let mut offset = start;
+
+    file.seek(SeekFrom::Start(offset))?;
+    while offset + RECORD_HEADER_LEN <= len {
+        let Ok(record) = read_record_header(file) else {
+            break;
+        };
+        let end = offset + RECORD_HEADER_LEN + record.len as u64;
+        if end > len {
+            break;
+        }
+
+        index.push(IndexEntry {
+            timestamp: record.timestamp,
+            offset,
+        });
+        file.seek_relative(record.len as i64)?;
+        offset = end;
+    }
+
+    debug!("recovered {} frames", index.len());
+    Ok(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAY_8X8: &[u8] = include_bytes!("../tests/data/gray_8x8.jpg");
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("etvr-{}-{name}", std::process::id()))
+    }
+
+    fn frames() -> Vec<Frame> {
+        let start = Instant::now();
+        (0..6u64)
+            .map(|i| {
+                let mut frame = if i % 2 == 0 {
+                    Frame::jpeg(GRAY_8X8.to_vec())
+                } else {
+                    Frame::packed(vec![i as u8; 64 * 48], 64, 48, PixelFormat::Gray8).unwrap()
+                };
+                frame.timestamp = start + Duration::from_millis(i * 10);
+                frame.sequence = i;
+                frame
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path = temp_path("round-trip.etvr");
+        let mut recorder = Recorder::create(&path)
+            .unwrap()
+            .with_source("serial://COM13")
+            .with_firmware("5.1.0");
+        for frame in frames() {
+            recorder.write(&frame).expect("frame should be written");
+        }
+        let summary = recorder.finish().expect("recording should finish");
+        assert_eq!(summary.frames, 6);
+        assert_eq!(summary.duration, Duration::from_millis(50));
+        assert_eq!(summary.bytes, std::fs::metadata(&path).unwrap().len());
+
+        let mut recording = Recording::open(&path).expect("recording should open");
+        assert_eq!(
+            recording.metadata().source.as_deref(),
+            Some("serial://COM13")
+        );
+        assert_eq!(recording.metadata().firmware.as_deref(), Some("5.1.0"));
+        assert_eq!(recording.len(), 6);
+        assert_eq!(recording.timestamp(3), Some(Duration::from_millis(30)));
+        assert_eq!(recording.position(Duration::from_millis(25)), 2);
+
+        for (i, expected) in frames().iter().enumerate() {
+            let frame = recording.read_frame(i).expect("frame should be readable");
+            assert_eq!(frame.data, expected.data);
+            assert_eq!(frame.format, expected.format);
+            assert_eq!(
+                (frame.width, frame.height),
+                (expected.width, expected.height)
+            );
+            assert_eq!(frame.sequence, i as u64);
+        }
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_recorder_thread() {
+        let path = temp_path("thread.etvr");
+        let broadcast = crate::broadcast::FrameBroadcast::default();
+        broadcast.reopen();
+
+        // the thread only starts after the queue overflowed
+        let subscription = broadcast.subscribe(4, crate::BackpressurePolicy::DropOldest);
+        for frame in frames() {
+            broadcast.send(&Arc::new(frame));
+        }
+        let recorder = RecorderThread::spawn(Recorder::create(&path).unwrap(), subscription);
+        assert!(recorder.is_recording());
+
+        let summary = recorder.finish().expect("recording should finish");
+        assert_eq!((summary.frames, summary.skipped), (4, 2));
+        let mut recording = Recording::open(&path).unwrap();
+        assert_eq!(recording.read_frame(0).unwrap().sequence, 2);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_cut_short() {
+        let path = temp_path("cut-short.etvr");
+        let mut recorder = Recorder::create(&path).unwrap();
+        for frame in frames() {
+            recorder.write(&frame).unwrap();
+        }
+        recorder.finish().unwrap();
+
+        // drop the index and half of the last record
+        let data = std::fs::read(&path).unwrap();
+        let cut = data.len() - TRAILER_LEN as usize - 6 * INDEX_ENTRY_LEN as usize - 10;
+        std::fs::write(&path, &data[..cut]).unwrap();
+
+        let mut recording = Recording::open(&path).expect("recording should recover");
+        assert_eq!(recording.len(), 5);
+        assert_eq!(recording.read_frame(4).unwrap().data, frames()[4].data);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_size_limit() {
+        let path = temp_path("size-limit.etvr");
+        let mut recorder = Recorder::create(&path).unwrap().with_max_size(1500);
+        for frame in frames() {
+            recorder.write(&frame).unwrap();
+        }
+        assert!(!recorder.is_recording());
+
+        let summary = recorder.finish().unwrap();
+        assert!(summary.truncated);
+        assert!(summary.frames < 6);
+        assert!(summary.bytes <= 1500);
+        assert_eq!(Recording::open(&path).unwrap().len() as u64, summary.frames);
+        std::fs::remove_file(path).unwrap();
+    }
+}
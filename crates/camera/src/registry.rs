@@ -0,0 +1,198 @@
+use std::fmt::{self, Debug};
+
+use log::{trace, warn};
+
+use crate::{CameraHandler, CameraSource, CameraState, backends::*};
+
+/// Creates a backend for a parsed source, the backend is connected afterwards
+/// using [`CameraSource::location`]
+pub type BackendFactory =
+    Box<dyn Fn(&CameraSource) -> Result<Box<dyn CameraHandler>, CameraState> + Send + Sync>;
+
+struct Backend {
+    name: String,
+    scheme: String,
+    factory: BackendFactory,
+}
+
+/// Maps URI schemes to camera backends
+/// - Backends outside of this crate are added via [`BackendRegistry::register`]
+///   and then opened through [`crate::Camera::open_with`]
+/// - [`BackendRegistry::default`] contains all built-in backends
+pub struct BackendRegistry {
+    backends: Vec<Backend>,
+}
+
+impl BackendRegistry {
+    /// Creates a registry without any backends
+    pub fn empty() -> Self {
+        Self {
+            backends: Vec::new(),
+        }
+    }
+
+    /// Registers a backend for the given URI scheme
+    /// - Schemes are matched case-insensitively
+    /// - Registering an already known scheme replaces the previous backend,
+    ///   allowing built-in backends to be overridden
+    pub fn register<F>(&mut self, name: &str, scheme: &str, factory: F) -> &mut Self
+    where
+        F: Fn(&CameraSource) -> Result<Box<dyn CameraHandler>, CameraState> + Send + Sync + 'static,
+    {
+        let scheme = scheme.to_ascii_lowercase();
+        self.backends.retain(|backend| backend.scheme != scheme);
+        self.backends.push(Backend {
+            name: name.to_string(),
+            scheme,
+            factory: Box::new(factory),
+        });
+
+        self
+    }
+
+    /// Returns the name of the backend registered for the given scheme
+    pub fn backend_name(&self, scheme: &str) -> Option<&str> {
+        self.find(scheme).map(|backend| backend.name.as_str())
+    }
+
+    /// Returns all registered `(name, scheme)` pairs
+    pub fn backends(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.backends
+            .iter()
+            .map(|backend| (backend.name.as_str(), backend.scheme.as_str()))
+    }
+
+    /// Creates the backend registered for the scheme of the given source
+    /// - Returns [`CameraState::InvalidSource`] if no backend handles the
+    ///   scheme
+    pub fn create(&self, source: &CameraSource) -> Result<Box<dyn CameraHandler>, CameraState> {
+        let Some(backend) = self.find(source.scheme()) else {
+            warn!("no camera backend for scheme {}: {source}", source.scheme());
+            return Err(CameraState::InvalidSource);
+        };
+
+        trace!("creating {} backend for {source}", backend.name);
+        (backend.factory)(source)
+    }
+
+    fn find(&self, scheme: &str) -> Option<&Backend> {
+        self.backends
+            .iter()
+            .find(|backend| backend.scheme.eq_ignore_ascii_case(scheme))
+    }
+}
+
+impl Default for BackendRegistry {
+    /// Creates a registry with all built-in backends
+    fn default() -> Self {
+        let mut registry = Self::empty();
+
+        registry.register("noop", "noop", |_| Ok(Box::new(NoOpCamera::init())));
+        registry.register("openiris", "serial", |source| {
+            let config = SerialConfig::from_source(source)?;
+            Ok(Box::new(OpenIrisCamera::init().with_serial_config(config)))
+        });
+        registry.register("mjpeg", "http", |_| Ok(Box::new(MjpegHttpCamera::init())));
+        registry.register("replay", "replay", |source| {
+            let mut camera = ReplayCamera::init();
+            if let Some(mode) = source.parse_param("mode")? {
+                camera = camera.with_mode(mode);
+            }
+            if let Some(looping) = source.parse_param("loop")? {
+                camera = camera.with_looping(looping);
+            }
+            Ok(Box::new(camera))
+        });
+        registry.register("synthetic", "synthetic", |source| {
+            let eye = SyntheticEye::from_source(source)?;
+            Ok(Box::new(SyntheticEyeCamera::init().with_eye(eye)))
+        });
+        for scheme in ["https", "rtsp", "file"] {
+            registry.register("opencv", scheme, |_| Ok(Box::new(OpenCVCamera::init())));
+        }
+        registry.register("opencv", "v4l2", |_| {
+            let camera = OpenCVCamera::init().with_api_preference(opencv::videoio::CAP_V4L2);
+            Ok(Box::new(camera))
+        });
+
+        registry
+    }
+}
+
+impl Debug for BackendRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.backends()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Camera, Frame, PixelFormat};
+
+    #[derive(Debug)]
+    struct LabRig {
+        exposure: u32,
+    }
+
+    impl CameraHandler for LabRig {
+        fn init() -> Self
+        where
+            Self: Sized,
+        {
+            Self { exposure: 0 }
+        }
+
+        fn get_frame(&mut self) -> Result<Frame, CameraState> {
+            let data = vec![self.exposure as u8; 4];
+            Frame::packed(data, 2, 2, PixelFormat::Gray8)
+        }
+
+        fn connect(&mut self, _source: String) -> Result<(), CameraState> {
+            Ok(())
+        }
+
+        fn disconnect(&mut self) {}
+    }
+
+    #[test]
+    fn test_custom_backend() {
+        let mut registry = BackendRegistry::default();
+        registry.register("lab-rig", "LabRig", |source| {
+            let exposure = source.parse_param("exposure")?.unwrap_or(1);
+            Ok(Box::new(LabRig { exposure }))
+        });
+        assert_eq!(registry.backend_name("labrig"), Some("lab-rig"));
+        assert_eq!(registry.backend_name("serial"), Some("openiris"));
+
+        let mut camera = Camera::open_with(&registry, "labrig://rig-1?exposure=7", 100)
+            .expect("registered backend should open");
+        let frame = camera
+            .get_frame_timeout(std::time::Duration::from_secs(1))
+            .expect("lab rig should deliver frames");
+        assert_eq!(frame.data, [7; 4]);
+        camera
+            .disconnect()
+            .expect("disconnect should always succeed");
+
+        assert_eq!(
+            Camera::open_with(&BackendRegistry::empty(), "noop://", 30).err(),
+            Some(CameraState::InvalidSource)
+        );
+    }
+
+    #[test]
+    fn test_override_builtin() {
+        let mut registry = BackendRegistry::default();
+        registry.register("lab-rig", "noop", |_| Ok(Box::new(LabRig::init())));
+
+        assert_eq!(registry.backend_name("noop"), Some("lab-rig"));
+        assert_eq!(
+            registry
+                .backends()
+                .filter(|(_, scheme)| *scheme == "noop")
+                .count(),
+            1
+        );
+    }
+}
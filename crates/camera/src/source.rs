@@ -0,0 +1,237 @@
+use std::{fmt, str::FromStr};
+
+use log::warn;
+
+use crate::CameraState;
+
+/// A camera source in URI form
+/// - `serial:///dev/ttyACM0?baud=3000000` or `serial://COM13`
+/// - `http://openiristracker.local:81/`
+/// - `file:///recordings/left.mjpeg`
+/// - `v4l2:///dev/video2`
+/// - `replay:///recordings/left.etvr?mode=fast&loop=true`
+/// - `synthetic://?fps=120&noise=8`
+/// - `noop://`
+///
+/// The scheme selects the backend, the remaining parts are interpreted by it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CameraSource {
+    uri: String,
+    scheme: String,
+    host: String,
+    path: String,
+    query: Vec<(String, String)>,
+}
+
+impl CameraSource {
+    /// Parses a source URI
+    /// - Returns [`CameraState::InvalidSource`] if the URI is malformed
+    pub fn parse(uri: &str) -> Result<CameraSource, CameraState> {
+        uri.parse()
+    }
+
+    /// Returns the URI this source was parsed from
+    pub fn as_str(&self) -> &str {
+        &self.uri
+    }
+
+    /// Returns the lowercase scheme, e.g. `serial`
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+
+    /// Returns the authority, e.g. `openiristracker.local:81`
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Returns the percent-decoded path, e.g. `/dev/ttyACM0`
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the percent-decoded value of the first query parameter with
+    /// the given name
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns all query parameters in order of appearance
+    pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.query
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Parses the query parameter with the given name
+    /// - Returns `Ok(None)` if the parameter is missing
+    /// - Returns [`CameraState::InvalidSource`] if the value cannot be parsed
+    pub fn parse_param<T: FromStr>(&self, name: &str) -> Result<Option<T>, CameraState> {
+        self.param(name)
+            .map(|value| {
+                value.parse().map_err(|_| {
+                    warn!("invalid value for {name} in {}: {value}", self.uri);
+                    CameraState::InvalidSource
+                })
+            })
+            .transpose()
+    }
+
+    /// Returns the location in the form the backend expects it, e.g. a device
+    /// path, a port name or the full URL for network streams
+    pub fn location(&self) -> String {
+        match self.scheme.as_str() {
+            "http" | "https" | "rtsp" => self.uri.clone(),
+            // `serial://COM13` or `v4l2://0`
+            _ if !self.host.is_empty() && self.path.is_empty() => self.host.clone(),
+            // `file:///C:/recordings/left.mjpeg`
+            _ if has_drive_letter(&self.path) => self.path[1..].to_string(),
+            _ => self.path.clone(),
+        }
+    }
+}
+
+impl FromStr for CameraSource {
+    type Err = CameraState;
+
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            warn!("invalid camera source {uri}: {reason}");
+            CameraState::InvalidSource
+        };
+
+        let (scheme, rest) = uri
+            .split_once("://")
+            .ok_or_else(|| invalid("missing scheme"))?;
+        let valid_scheme = scheme
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+        if !valid_scheme {
+            return Err(invalid("malformed scheme"));
+        }
+
+        let rest = rest.split_once('#').map_or(rest, |(rest, _fragment)| rest);
+        let (rest, query) = rest.split_once('?').unwrap_or((rest, ""));
+        let (host, path) = match rest.find('/') {
+            Some(index) => rest.split_at(index),
+            None => (rest, ""),
+        };
+
+        let query = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                Ok((percent_decode(key)?, percent_decode(value)?))
+            })
+            .collect::<Result<_, &str>>()
+            .map_err(invalid)?;
+
+        Ok(CameraSource {
+            uri: uri.to_string(),
+            scheme: scheme.to_ascii_lowercase(),
+            host: host.to_string(),
+            path: percent_decode(path).map_err(invalid)?,
+            query,
+        })
+    }
+}
+
+impl fmt::Display for CameraSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.uri)
+    }
+}
+
+fn percent_decode(input: &str) -> Result<String, &'static str> {
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut iter = input.bytes();
+
+    while let Some(byte) = iter.next() {
+        if byte != b'%' {
+            bytes.push(byte);
+            continue;
+        }
+
+        let hex = [iter.next(), iter.next()];
+        let [Some(high), Some(low)] = hex else {
+            return Err("truncated percent encoding");
+        };
+        let decoded = std::str::from_utf8(&[high, low])
+            .ok()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .ok_or("invalid percent encoding")?;
+        bytes.push(decoded);
+    }
+
+    String::from_utf8(bytes).map_err(|_| "percent encoding is not valid utf-8")
+}
+
+fn has_drive_letter(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 3 && bytes[0] == b'/' && bytes[1].is_ascii_alphabetic() && bytes[2] == b':'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sources() {
+        let source = CameraSource::parse("serial:///dev/ttyACM0?baud=3000000").unwrap();
+        assert_eq!(source.scheme(), "serial");
+        assert_eq!(source.location(), "/dev/ttyACM0");
+        assert_eq!(source.parse_param::<u32>("baud"), Ok(Some(3_000_000)));
+
+        let source = CameraSource::parse("serial://COM13").unwrap();
+        assert_eq!(source.location(), "COM13");
+        assert_eq!(source.parse_param::<u32>("baud"), Ok(None));
+
+        let source = CameraSource::parse("http://openiristracker.local:81/").unwrap();
+        assert_eq!(source.host(), "openiristracker.local:81");
+        assert_eq!(source.location(), "http://openiristracker.local:81/");
+
+        let source = CameraSource::parse("file:///recordings/left%20eye.mjpeg").unwrap();
+        assert_eq!(source.location(), "/recordings/left eye.mjpeg");
+
+        let source = CameraSource::parse("file:///C:/recordings/left.mjpeg").unwrap();
+        assert_eq!(source.location(), "C:/recordings/left.mjpeg");
+
+        let source = CameraSource::parse("v4l2:///dev/video2").unwrap();
+        assert_eq!(source.location(), "/dev/video2");
+
+        let source = CameraSource::parse("NoOp://").unwrap();
+        assert_eq!(source.scheme(), "noop");
+        assert_eq!(source.location(), "");
+    }
+
+    #[test]
+    fn test_invalid_sources() {
+        for uri in [
+            "COM13",
+            "/dev/ttyACM0",
+            "://x",
+            "1serial://x",
+            "file:///a%2",
+        ] {
+            assert_eq!(
+                CameraSource::parse(uri),
+                Err(CameraState::InvalidSource),
+                "{uri}"
+            );
+        }
+
+        let source = CameraSource::parse("serial://COM13?baud=fast").unwrap();
+        assert_eq!(
+            source.parse_param::<u32>("baud"),
+            Err(CameraState::InvalidSource)
+        );
+    }
+}
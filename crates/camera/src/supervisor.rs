@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+/// Controls how a supervised [`crate::Camera`] recovers from a stalled or
+/// failing backend
+/// - The handler thread disconnects the backend and reconnects it to the last
+///   source, waiting exponentially longer between failed attempts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Consecutive capture errors after which the backend is reconnected
+    pub error_threshold: u32,
+    /// Time without a successful frame after which a failing backend is
+    /// considered stalled, regardless of the error count
+    pub stall_timeout: Duration,
+    /// Wait time after the first failed reconnect attempt
+    pub initial_backoff: Duration,
+    /// Upper bound for the wait time between reconnect attempts
+    pub max_backoff: Duration,
+    /// Give up after this many failed reconnect attempts
+    /// - `None` keeps retrying until the camera is disconnected
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            error_threshold: 10,
+            stall_timeout: Duration::from_secs(2),
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(10),
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Returns the wait time after the given amount of failed attempts
+    pub fn backoff(&self, failed_attempts: u32) -> Duration {
+        let exponent = failed_attempts.saturating_sub(1).min(16);
+        self.initial_backoff
+            .saturating_mul(2u32.pow(exponent))
+            .min(self.max_backoff)
+    }
+
+    /// Whether the backend should be reconnected
+    pub fn is_stalled(&self, errors: u32, since_last_frame: Duration) -> bool {
+        errors >= self.error_threshold || (errors > 0 && since_last_frame >= self.stall_timeout)
+    }
+
+    /// Whether another reconnect attempt is allowed after the given amount of
+    /// failed attempts
+    pub fn should_retry(&self, failed_attempts: u32) -> bool {
+        self.max_attempts.is_none_or(|max| failed_attempts < max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_is_capped() {
+        let policy = ReconnectPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+            ..Default::default()
+        };
+
+        let backoff: Vec<_> = (1..=5).map(|i| policy.backoff(i).as_millis()).collect();
+        assert_eq!(backoff, [100, 200, 400, 500, 500]);
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_millis(500));
+    }
+}